@@ -0,0 +1,56 @@
+#[macro_use]
+extern crate criterion;
+#[macro_use]
+extern crate serde_scan;
+
+use criterion::{black_box, Criterion};
+use serde_scan::Pattern;
+
+fn integers(c: &mut Criterion) {
+    let input: String = (0..1_000_000u32).map(|i| format!("{} ", i)).collect();
+
+    c.bench_function("1M integers", |b| {
+        b.iter(|| serde_scan::from_str::<Vec<u32>>(black_box(&input)).unwrap())
+    });
+}
+
+fn words(c: &mut Criterion) {
+    let input = "lorem ipsum\tdolor sit\namet ".repeat(200_000);
+
+    c.bench_function("1M words", |b| {
+        b.iter(|| serde_scan::from_str::<Vec<&str>>(black_box(&input)).unwrap())
+    });
+}
+
+fn patterns(c: &mut Criterion) {
+    let input: String = (0..10_000u32)
+        .map(|i| format!("#{} @ {},{}: {}x{}\n", i, i % 1000, i % 700, i % 30, i % 20))
+        .collect();
+
+    let mut group = c.benchmark_group("10k pattern lines");
+
+    group.bench_function("scan!", |b| {
+        b.iter(|| {
+            for line in black_box(&input).lines() {
+                let claim: (u32, u32, u32, u32, u32) = scan!("#{} @ {},{}: {}x{}" <- line).unwrap();
+                black_box(claim);
+            }
+        })
+    });
+
+    group.bench_function("Pattern::parse", |b| {
+        let pattern = Pattern::new("#{} @ {},{}: {}x{}");
+
+        b.iter(|| {
+            for line in black_box(&input).lines() {
+                let claim: (u32, u32, u32, u32, u32) = pattern.parse(line).unwrap();
+                black_box(claim);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, integers, words, patterns);
+criterion_main!(benches);
@@ -16,8 +16,8 @@ struct Claim {
 fn main() {
     let input = include_str!("scan.txt");
 
-    for l in input.lines() {
-        let c: Claim = scan!("#{} @ {},{}: {}x{}" <- l).unwrap();
+    for c in scan_all!(Claim; "#{} @ {},{}: {}x{}" <- input) {
+        let c = c.unwrap();
 
         println!(
             "claim no. {}. start: ({},{}), area: {}",
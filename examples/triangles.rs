@@ -19,15 +19,9 @@ impl Triangle {
 
 fn main() {
     let n: usize = serde_scan::next_line().unwrap();
-    let mut valid = 0;
+    let triangles: Vec<Triangle> = serde_scan::next_n_lines(n).unwrap();
 
-    for _ in 0..n {
-        let t: Triangle = serde_scan::next_line().unwrap();
-
-        if t.is_valid() {
-            valid += 1;
-        }
-    }
+    let valid = triangles.iter().filter(|t| t.is_valid()).count();
 
     println!("{} out of {} triangles are valid.", valid, n);
 }
@@ -0,0 +1,279 @@
+//! Procedural helpers for the `scan!` macro in `serde_scan`.
+//!
+//! Nothing in here is meant to be used directly.
+
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
+
+/// Expand a `scan!` pattern literal into the type it deserializes to.
+///
+/// Called as `scan_type!($crate; "pattern")`.
+///
+/// `{}` placeholders are inferred (`_`), while `{Type}` placeholders use the given type.
+/// `{_}` placeholders are read as `IgnoredAny`, to be dropped by `scan_select!`.
+/// A trailing `{*}` placeholder is read as a `&str`.
+/// A pattern with only one placeholder produces that type directly, otherwise a tuple.
+#[proc_macro]
+pub fn scan_type(input: TokenStream) -> TokenStream {
+    let (krate, slots) = match parse_input(input) {
+        Ok(input) => input,
+        Err(e) => return e,
+    };
+
+    if slots.iter().all(|slot| *slot == Slot::Infer) {
+        return "_".parse().unwrap();
+    }
+
+    let mut types = slots.iter().map(|slot| match *slot {
+        Slot::Infer => "_".parse().unwrap(),
+        Slot::Skip => {
+            let mut path = krate.clone();
+            path.extend("::__IgnoredAny".parse::<TokenStream>());
+            path
+        }
+        Slot::Rest => "&str".parse().unwrap(),
+        Slot::Type(ref ty) => ty.parse().unwrap(),
+    });
+
+    if slots.len() == 1 {
+        return types.next().unwrap();
+    }
+
+    let mut tuple = TokenStream::new();
+    for ty in types {
+        tuple.extend(ty);
+        tuple.extend(",".parse::<TokenStream>());
+    }
+
+    TokenTree::Group(Group::new(Delimiter::Parenthesis, tuple)).into()
+}
+
+/// Expand a `scan!` pattern literal into a closure dropping the `{_}` captures
+/// from the value produced by `scan_type!`.
+///
+/// Called as `scan_select!($crate; "pattern")`.
+#[proc_macro]
+pub fn scan_select(input: TokenStream) -> TokenStream {
+    let slots = match parse_input(input) {
+        Ok((_, slots)) => slots,
+        Err(e) => return e,
+    };
+
+    if !slots.contains(&Slot::Skip) {
+        return "|value| value".parse().unwrap();
+    }
+
+    let names: Vec<String> = slots
+        .iter()
+        .enumerate()
+        .map(|(i, slot)| match *slot {
+            Slot::Skip => "_".to_string(),
+            _ => format!("__{}", i),
+        })
+        .collect();
+    let kept: Vec<&str> = names
+        .iter()
+        .filter(|name| *name != "_")
+        .map(|name| name.as_str())
+        .collect();
+
+    let pattern = if names.len() == 1 {
+        "_".to_string()
+    } else {
+        format!("({},)", names.join(", "))
+    };
+
+    let value = match kept.len() {
+        0 => "()".to_string(),
+        1 => kept[0].to_string(),
+        _ => format!("({},)", kept.join(", ")),
+    };
+
+    format!("|{}| {}", pattern, value).parse().unwrap()
+}
+
+/// Check a `scan!(Type; "pattern")` pattern, expanding to the pattern literal.
+///
+/// Called as `scan_untyped!($crate; "pattern")`.
+///
+/// The captures fill the type's fields in order, so `{_}` and `{Type}` placeholders
+/// have no meaning there, and are a compile error rather than silently ignored.
+#[proc_macro]
+pub fn scan_untyped(input: TokenStream) -> TokenStream {
+    let lit = input.clone().into_iter().last();
+
+    let slots = match parse_input(input) {
+        Ok((_, slots)) => slots,
+        Err(e) => return e,
+    };
+
+    let typed = slots
+        .iter()
+        .any(|slot| matches!(*slot, Slot::Skip | Slot::Type(_)));
+
+    if typed {
+        return compile_error(
+            "`{_}` and `{Type}` placeholders can't be used with `scan!(Type; ...)`, \
+             since every capture fills the next field of the type",
+        );
+    }
+
+    lit.into_iter().collect()
+}
+
+/// Expand a `scan!` pattern and a struct's field list into a closure building the struct
+/// from the value produced by `scan_type!` and `scan_select!`.
+///
+/// Called as `scan_fields!($crate; "pattern"; Type; field, ...)`.
+///
+/// Each field takes one capture, in order, so the number of fields has to match the
+/// number of captures, not counting `{_}`.
+#[proc_macro]
+pub fn scan_fields(input: TokenStream) -> TokenStream {
+    let mut parts = vec![TokenStream::new()];
+
+    for token in input {
+        match token {
+            TokenTree::Punct(ref p) if p.as_char() == ';' && parts.len() < 4 => {
+                parts.push(TokenStream::new());
+            }
+            token => parts.last_mut().unwrap().extend(Some(token)),
+        }
+    }
+
+    let (fields, target) = match (parts.pop(), parts.pop()) {
+        (Some(fields), Some(target)) if parts.len() == 2 => (fields, target),
+        _ => return compile_error("expected `$crate; \"pattern\"; Type; fields`"),
+    };
+
+    // put `$crate; "pattern"` back together
+    let pattern = parts.pop().unwrap();
+    let mut input = parts.pop().unwrap();
+    input.extend(";".parse::<TokenStream>());
+    input.extend(pattern);
+
+    let slots = match parse_input(input) {
+        Ok((_, slots)) => slots,
+        Err(e) => return e,
+    };
+
+    let fields: Vec<String> = fields
+        .into_iter()
+        .filter_map(|token| match token {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        .collect();
+    let captures = slots.iter().filter(|slot| **slot != Slot::Skip).count();
+
+    if fields.len() != captures {
+        let msg = format!(
+            "`{}` is given {} fields, but the pattern has {} captures",
+            target,
+            fields.len(),
+            captures
+        );
+        return compile_error(&msg);
+    }
+
+    let binding = match fields.len() {
+        0 => "()".to_string(),
+        1 => fields[0].clone(),
+        _ => format!("({},)", fields.join(", ")),
+    };
+
+    let mut closure: TokenStream = format!("|{}|", binding).parse().unwrap();
+    closure.extend(target);
+    closure.extend(Some(TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        fields.join(", ").parse().unwrap(),
+    ))));
+    closure
+}
+
+#[derive(PartialEq)]
+enum Slot {
+    Infer,
+    Skip,
+    Rest,
+    Type(String),
+}
+
+/// split `$crate; "pattern"` into the crate path and the placeholders of the pattern
+fn parse_input(input: TokenStream) -> Result<(TokenStream, Vec<Slot>), TokenStream> {
+    let mut krate = TokenStream::new();
+    let mut tokens = input.into_iter();
+
+    for token in tokens.by_ref() {
+        match token {
+            TokenTree::Punct(ref p) if p.as_char() == ';' => break,
+            token => krate.extend(Some(token)),
+        }
+    }
+
+    let lit = match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Literal(lit)), None) => lit.to_string(),
+        _ => String::new(),
+    };
+
+    match unquote(&lit).map(slots) {
+        Some(Ok(slots)) => Ok((krate, slots)),
+        Some(Err(msg)) => Err(compile_error(msg)),
+        None => Err(compile_error("scan pattern must be a string literal")),
+    }
+}
+
+fn compile_error(msg: &str) -> TokenStream {
+    format!("compile_error!({:?})", msg).parse().unwrap()
+}
+
+/// strip the quotes off of a string literal, leaving escapes in place
+fn unquote(lit: &str) -> Option<&str> {
+    if let Some(raw) = lit.strip_prefix('r') {
+        let hashes = raw.chars().take_while(|&c| c == '#').count();
+        let start = 2 + hashes;
+        let end = lit.len().checked_sub(1 + hashes)?;
+
+        lit.get(start..end)
+    } else if lit.starts_with('"') && lit.len() >= 2 {
+        lit.get(1..lit.len() - 1)
+    } else {
+        None
+    }
+}
+
+fn slots(pattern: &str) -> Result<Vec<Slot>, &'static str> {
+    let mut slots = Vec::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if slots.last() == Some(&Slot::Rest) {
+            return Err("`{*}` must be the last thing in a scan pattern");
+        }
+
+        match ch {
+            // skip over escapes, so `\u{..}` isn't mistaken for a placeholder
+            '\\' if chars.next() == Some('u') => {
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+            }
+            // `{{` is a literal brace
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let ty: String = chars.by_ref().take_while(|&c| c != '}').collect();
+
+                slots.push(match ty.trim() {
+                    "" => Slot::Infer,
+                    "_" => Slot::Skip,
+                    "*" => Slot::Rest,
+                    ty => Slot::Type(ty.to_string()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok(slots)
+}
@@ -0,0 +1,49 @@
+use lib::num::ParseIntError;
+
+/// Integer types that can be parsed in any radix.
+pub trait Integer: Sized {
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntError>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty)*) => {
+        $(
+            impl Integer for $t {
+                fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntError> {
+                    <$t>::from_str_radix(s, radix)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+/// Parse an integer, respecting `0x`, `0o`, and `0b` prefixes.
+pub fn parse<T: Integer>(s: &str) -> Result<T, ParseIntError> {
+    let (negative, unsigned) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+
+    let radix = match unsigned.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0o") | Some("0O") => 8,
+        Some("0b") | Some("0B") => 2,
+        _ => return T::from_str_radix(s, 10),
+    };
+
+    let digits = &unsigned[2..];
+
+    // `from_str_radix` takes a sign of its own, which can't come after the prefix
+    if digits.starts_with(['+', '-']) {
+        return T::from_str_radix("-", radix);
+    }
+
+    if negative {
+        T::from_str_radix(&format!("-{}", digits), radix)
+    } else {
+        T::from_str_radix(digits, radix)
+    }
+}
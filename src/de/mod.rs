@@ -1,55 +1,459 @@
 use errors::*;
-use std::iter::{Filter, Peekable};
-use std::str::{FromStr, Split};
+use lib::any;
+use lib::borrow::Cow;
+use lib::fmt;
+use lib::iter::{Filter, Peekable};
+use lib::num::IntErrorKind;
+use lib::option;
+use lib::str::{FromStr, Split, SplitWhitespace};
+use lib::string::{String, ToString};
+use lib::vec::Vec;
 
-use serde::de::{self, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::de::{self, Deserialize, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor};
 
-pub struct Deserializer<'de, F>
+mod int;
+
+use self::int::Integer;
+
+/// Tokens split from a string by a predicate.
+pub type Tokens<'de, F> = Filter<Split<'de, F>, fn(&&str) -> bool>;
+/// Tokens split from a string on whitespace.
+pub type StrTokens<'de> = SplitWhitespace<'de>;
+
+/// Settings that change how values are read from the token stream.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// maps start with a token giving the number of entries
+    pub counted_maps: bool,
+    /// sequences start with a token giving the number of elements
+    pub counted_seqs: bool,
+    /// token standing in for a missing optional value
+    pub none_token: String,
+    /// character splitting map entries like `key=value` within a single token
+    pub kv_separator: Option<char>,
+    /// strings starting with `"` run until the closing quote
+    pub quoted_strings: bool,
+    /// enum variants are matched ignoring ascii case
+    pub case_insensitive_variants: bool,
+    /// every integer is in this radix, with no prefix
+    pub radix: Option<u32>,
+    /// floats must not be infinite or NaN
+    pub reject_non_finite: bool,
+    /// byte buffers are read from the bytes of a single token
+    pub raw_bytes: bool,
+    /// integers are read as decimals, multiplied by ten to this power
+    pub decimal_scale: Option<u32>,
+    /// characters ignored inside numbers, like the `_` in `1_000`
+    pub digit_separators: String,
+    /// a unit variant has to be the last token of the input
+    pub strict_unit_variants: bool,
+    /// a single character that isn't a digit reads as its code point in integer fields
+    pub char_as_code: bool,
+    /// parse errors hold the input around the token that failed
+    pub verbose_errors: bool,
+    /// chars can be written as backslash escapes, like `\n`
+    pub char_escapes: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            counted_maps: false,
+            counted_seqs: false,
+            none_token: String::from("-"),
+            kv_separator: None,
+            quoted_strings: true,
+            case_insensitive_variants: false,
+            radix: None,
+            reject_non_finite: false,
+            raw_bytes: false,
+            decimal_scale: None,
+            digit_separators: String::new(),
+            strict_unit_variants: false,
+            char_as_code: false,
+            verbose_errors: false,
+            char_escapes: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Deserializer<'de, I>
+where
+    I: Iterator<Item = &'de str>,
+{
+    iter: Peekable<I>,
+    /// the string tokens are split from, if any
+    input: &'de str,
+    /// byte offset of the start of the last token read
+    start: usize,
+    /// byte offset just past the last token read
+    end: usize,
+    consumed: usize,
+    /// nothing has been read yet, and the input holds a single value
+    top_level: bool,
+    /// how many sequences, structs, maps and enums the current value is inside of
+    depth: usize,
+    config: Config,
+}
+
+/// Split a string into tokens on any whitespace.
+pub fn str_tokens<'de>(s: &'de str) -> StrTokens<'de> {
+    // the same tokens as `split`, but quite a bit faster
+    s.strip_prefix('\u{feff}').unwrap_or(s).split_whitespace()
+}
+
+/// Split a string into tokens on characters matching `f`, skipping empty tokens.
+pub fn split<'de, F>(f: F, s: &'de str) -> Tokens<'de, F>
 where
     F: FnMut(char) -> bool,
 {
-    iter: Peekable<Filter<Split<'de, F>, fn(&&str) -> bool>>,
+    fn is_not_empty(s: &&str) -> bool {
+        !s.is_empty()
+    }
+
+    // a byte order mark isn't part of the first token
+    let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+
+    s.split(f).filter(is_not_empty as fn(&&str) -> bool)
+}
+
+impl<'de> Deserializer<'de, StrTokens<'de>> {
+    pub fn from_str(s: &'de str) -> Self {
+        let mut de = Deserializer::from_tokens(str_tokens(s));
+        de.input = s;
+        de
+    }
 }
 
-impl<'de, F> Deserializer<'de, F>
+impl<'de, F> Deserializer<'de, Tokens<'de, F>>
 where
     F: FnMut(char) -> bool,
 {
-    pub fn from_str(s: &'de str) -> Deserializer<impl FnMut(char) -> bool> {
-        fn is_not_empty(s: &&str) -> bool {
-            !s.is_empty()
+    pub fn from_closure(f: F, s: &'de str) -> Self {
+        let mut de = Deserializer::from_tokens(split(f, s));
+        de.input = s;
+        de
+    }
+}
+
+impl<'de, I> Deserializer<'de, I>
+where
+    I: Iterator<Item = &'de str>,
+{
+    /// Read from tokens that have already been split up.
+    pub fn from_tokens(tokens: I) -> Self {
+        Deserializer {
+            iter: tokens.peekable(),
+            input: "",
+            start: 0,
+            end: 0,
+            consumed: 0,
+            top_level: false,
+            depth: 0,
+            config: Config::default(),
         }
-        let is_not_empty = is_not_empty as fn(&&str) -> bool;
+    }
 
-        fn is_whitespace(c: char) -> bool {
-            c.is_whitespace()
+    /// Read the whole input as one value, so `deserialize_any` reads it as a sequence
+    /// when there's more than one token.
+    ///
+    /// Only for values that can be read from any number of tokens, like a
+    /// `serde_json::Value`. Anything else is better off reporting the extra tokens.
+    pub fn whole_input(self) -> Self {
+        let mut new = self;
+        new.top_level = true;
+        new
+    }
+
+    pub fn with_config(self, config: Config) -> Self {
+        let mut new = self;
+        new.config = config;
+        new
+    }
+
+    pub fn deserialize<T: Deserialize<'de>>(&mut self) -> Result<T, ScanError> {
+        T::deserialize(&mut *self).map_err(|e| self.annotate(e, self.start))
+    }
+
+    /// Parse a sequence, where the first token is the number of elements.
+    pub fn deserialize_counted<T: Deserialize<'de>>(&mut self) -> Result<T, ScanError> {
+        T::deserialize(CountedSeq(&mut *self)).map_err(|e| self.annotate(e, self.start))
+    }
+
+    /// record where an error happened, and with `verbose_errors`, the input from
+    /// `offset` to the end of its line, cut short at 40 characters
+    fn annotate(&self, e: ScanError, offset: usize) -> ScanError {
+        let e = e.at_token(self.consumed);
+
+        if !self.config.verbose_errors {
+            return e;
         }
 
-        Deserializer {
-            iter: s.split(is_whitespace).filter(is_not_empty).peekable(),
+        let rest = self.input.get(offset..).unwrap_or("").trim_start();
+        let line = rest.lines().next().unwrap_or("");
+
+        match line.char_indices().nth(40) {
+            Some((i, _)) => e.with_near(&line[..i]),
+            None => e.with_near(line),
         }
     }
 
-    pub fn from_closure(f: F, s: &'de str) -> Self {
-        fn is_not_empty(s: &&str) -> bool {
-            !s.is_empty()
-        }
-        let is_not_empty = is_not_empty as fn(&&str) -> bool;
+    /// The part of the input after the last token read.
+    pub fn remainder(&self) -> &'de str {
+        &self.input[self.end..]
+    }
 
-        Deserializer {
-            iter: s.split(f).filter(is_not_empty).peekable(),
+    /// Check if there are no tokens left.
+    pub fn is_empty(&mut self) -> bool {
+        self.iter.peek().is_none()
+    }
+
+    /// Check if there are tokens left, without reading ahead.
+    pub fn has_tokens(&self) -> bool
+    where
+        I: Clone,
+    {
+        self.iter.clone().peek().is_some()
+    }
+
+    /// Throw out any remaining tokens.
+    pub fn skip_rest(&mut self) {
+        self.iter.by_ref().for_each(drop);
+    }
+
+    /// Check that every token has been used.
+    pub fn end(&mut self) -> Result<(), ScanError> {
+        match self.iter.peek() {
+            Some(s) => {
+                let e = ScanError::TrailingTokens {
+                    token: s.to_string(),
+                    at_token: self.consumed + 1,
+                    near: None,
+                };
+                Err(self.annotate(e, self.end))
+            }
+            None => Ok(()),
         }
     }
 
+    /// a deserializer over part of the last token, sharing this one's settings and position
+    fn sub_token(&self, s: &'de str) -> Deserializer<'de, option::IntoIter<&'de str>> {
+        let token = Some(s).filter(|s| !s.is_empty());
+
+        let mut sub = Deserializer::from_tokens(token.into_iter()).with_config(self.config.clone());
+        sub.consumed = self.consumed - 1;
+        sub
+    }
+
     fn parse_next<T: FromStr>(&mut self) -> Result<T, ScanError> {
-        match self.iter.next() {
-            Some(s) => s.parse().map_err(|_| ScanError::De),
-            None => Err(ScanError::EOF),
+        let s = self.next()?;
+
+        self.digits(s)
+            .parse()
+            .map_err(|_| self.parse_failed::<T>(s))
+    }
+
+    fn parse_int<T: Integer>(&mut self) -> Result<T, ScanError> {
+        let s = self.next()?;
+        let digits = self.digits(s);
+
+        let parsed = match (
+            self.char_code(s),
+            self.config.decimal_scale,
+            self.config.radix,
+        ) {
+            (Some(code), _, _) => T::from_str_radix(&code.to_string(), 10),
+            (None, Some(scale), _) => T::from_str_radix(&self.unscale(&digits, scale)?, 10),
+            (None, None, Some(radix)) => T::from_str_radix(&digits, radix),
+            (None, None, None) => int::parse(&digits),
+        };
+
+        parsed.map_err(|e| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => ScanError::OutOfRange {
+                token: s.to_string(),
+                target: any::type_name::<T>(),
+                at_token: self.consumed,
+                near: None,
+            },
+            _ => self.parse_failed::<T>(s),
+        })
+    }
+
+    /// the code point of a token that's a single character, but not a digit
+    fn char_code(&self, s: &str) -> Option<u32> {
+        if !self.config.char_as_code {
+            return None;
+        }
+
+        let radix = self.config.radix.unwrap_or(10);
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) if !ch.is_digit(radix) => Some(ch as u32),
+            _ => None,
+        }
+    }
+
+    /// the token with any digit separators taken out
+    fn digits<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let separators = &self.config.digit_separators;
+
+        if s.contains(|c| separators.contains(c)) {
+            Cow::Owned(s.replace(|c| separators.contains(c), ""))
+        } else {
+            Cow::Borrowed(s)
+        }
+    }
+
+    /// shift the decimal point of `s` right by `scale` digits, so `12.3` with a scale of 2 is `1230`
+    fn unscale(&self, s: &str, scale: u32) -> Result<String, ScanError> {
+        let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+        let unsigned = whole.strip_prefix(['+', '-']).unwrap_or(whole);
+        let padding = (scale as usize).checked_sub(frac.len());
+
+        // a sign on its own, or a lone `.`, isn't a number
+        let digits = unsigned.len() + frac.len() > 0
+            && unsigned.bytes().all(|b| b.is_ascii_digit())
+            && frac.bytes().all(|b| b.is_ascii_digit());
+
+        match padding {
+            Some(padding) if digits => Ok(format!("{}{}{}", whole, frac, "0".repeat(padding))),
+            Some(_) => Err(ScanError::De {
+                msg: format!("expected a decimal number, got `{}`", s),
+                at_token: self.consumed,
+                near: None,
+            }),
+            None => Err(ScanError::De {
+                msg: format!(
+                    "expected at most {} digits after the decimal point, got `{}`",
+                    scale, s
+                ),
+                at_token: self.consumed,
+                near: None,
+            }),
+        }
+    }
+
+    fn parse_bool(&mut self) -> Result<bool, ScanError> {
+        const TRUE: &[&str] = &["true", "t", "yes", "y", "1"];
+        const FALSE: &[&str] = &["false", "f", "no", "n", "0"];
+
+        let s = self.next()?;
+
+        if TRUE.iter().any(|t| t.eq_ignore_ascii_case(s)) {
+            Ok(true)
+        } else if FALSE.iter().any(|f| f.eq_ignore_ascii_case(s)) {
+            Ok(false)
+        } else {
+            Err(self.parse_failed::<bool>(s))
+        }
+    }
+
+    fn parse_char(&mut self) -> Result<char, ScanError> {
+        let s = self.next()?;
+        let mut chars = s.chars();
+
+        // a char is a single unicode scalar value, so combining sequences don't count
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(ch), None, _) => Ok(ch),
+            (Some('\\'), Some(escape), None) if self.config.char_escapes => match escape {
+                'n' => Ok('\n'),
+                'r' => Ok('\r'),
+                't' => Ok('\t'),
+                's' => Ok(' '),
+                '0' => Ok('\0'),
+                '\\' => Ok('\\'),
+                _ => Err(ScanError::De {
+                    msg: format!("unknown character escape `{}`", s),
+                    at_token: self.consumed,
+                    near: None,
+                }),
+            },
+            _ => Err(ScanError::De {
+                msg: format!("expected single character, got `{}`", s),
+                at_token: self.consumed,
+                near: None,
+            }),
+        }
+    }
+
+    /// read a `"quoted phrase"`, exactly as it appears between the quotes
+    ///
+    /// without the input to slice it from, its words are joined with single spaces.
+    fn parse_quoted(&mut self) -> Result<Cow<'de, str>, ScanError> {
+        let mut token = &self.next()?[1..];
+        let mut words = Vec::new();
+        let start = self.start + 1;
+
+        loop {
+            let (word, done) = match token.strip_suffix('"') {
+                Some(word) => (word, true),
+                None => (token, false),
+            };
+
+            if !word.is_empty() {
+                words.push(word);
+            }
+            if done {
+                if self.input.is_empty() {
+                    return Ok(Cow::Owned(words.join(" ")));
+                }
+                return Ok(Cow::Borrowed(&self.input[start..self.end - 1]));
+            }
+
+            token = match self.next() {
+                Ok(token) => token,
+                Err(ScanError::EOF) => {
+                    return Err(ScanError::De {
+                        msg: String::from("unterminated quoted string"),
+                        at_token: self.consumed,
+                        near: None,
+                    })
+                }
+                Err(e) => return Err(e),
+            };
+        }
+    }
+
+    /// called after parsing a float, so the last token is the float
+    fn check_finite(&self, finite: bool) -> Result<(), ScanError> {
+        if finite || !self.config.reject_non_finite {
+            Ok(())
+        } else {
+            Err(ScanError::De {
+                msg: String::from("expected a finite number"),
+                at_token: self.consumed,
+                near: None,
+            })
+        }
+    }
+
+    fn parse_failed<T>(&self, token: &str) -> ScanError {
+        ScanError::ParseFailed {
+            token: token.to_string(),
+            target: any::type_name::<T>(),
+            at_token: self.consumed,
+            near: None,
         }
     }
 
     fn next(&mut self) -> Result<&'de str, ScanError> {
-        self.iter.next().ok_or(ScanError::EOF)
+        let s = self.iter.next().ok_or(ScanError::EOF)?;
+        self.consumed += 1;
+        self.top_level = false;
+
+        // tokens split from the input are slices of it, so their position can be found from their address
+        let offset = (s.as_ptr() as usize).wrapping_sub(self.input.as_ptr() as usize);
+        match offset.checked_add(s.len()) {
+            Some(end) if end <= self.input.len() => {
+                self.start = offset;
+                self.end = end;
+            }
+            _ => {}
+        }
+
+        Ok(s)
     }
 
     fn peek(&mut self) -> Option<&&'de str> {
@@ -57,33 +461,112 @@ where
     }
 }
 
-enum NextValue {
+/// What a token looks like, checked in the order listed.
+///
+/// This is how `deserialize_any` picks a type, so integers like `007`, `+5` or `0x10`
+/// are always integers, while anything float-shaped, like `5.`, `5e2`, `inf` or `nan`,
+/// is a float. Tokens with digit separators, like `5_000`, are strings, unless
+/// `ScanOptions::digit_separators` takes the separators out first.
+///
+/// Integers are recognized the same way they're read: with `ScanOptions::radix`,
+/// `deserialize_any` takes tokens like `ff` as integers in that radix, and a prefixed
+/// token like `0x10` as a string, since prefixes aren't allowed.
+///
+/// `#[serde(untagged)]` enums are read through `deserialize_any` too, so the token's kind
+/// decides which variants can match, before variant order does. A digit like `7` is an
+/// integer, so it never matches a `char` variant, even one listed first.
+///
+/// Types that read themselves with `deserialize_str` always get the token as written,
+/// without it being classified. Ones that use `deserialize_any`, like `rust_decimal::Decimal`
+/// by default, see a float-shaped token as an `f64`, which can lose digits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// fits in a `u64`
     Unsigned,
+    /// fits in an `i64`
     Integer,
+    /// fits in a `u128`
+    Unsigned128,
+    /// fits in an `i128`
+    Integer128,
+    /// parses as an `f64`
     Float,
+    /// a single character
     Char,
+    /// anything else
     String,
 }
 
-impl NextValue {
-    fn new(next: &str) -> Self {
-        if next.parse::<u64>().is_ok() {
-            NextValue::Unsigned
-        } else if next.parse::<i64>().is_ok() {
-            NextValue::Integer
+impl TokenKind {
+    pub(crate) fn new(next: &str) -> Self {
+        TokenKind::in_radix(next, None)
+    }
+
+    /// integers are checked the same way `parse_int` reads them: with a fixed radix
+    /// and no prefix if one is set, or with an optional `0x`, `0o` or `0b` prefix if not
+    pub(crate) fn in_radix(next: &str, radix: Option<u32>) -> Self {
+        fn is_int<T: Integer>(s: &str, radix: Option<u32>) -> bool {
+            match radix {
+                Some(radix) => T::from_str_radix(s, radix).is_ok(),
+                None => int::parse::<T>(s).is_ok(),
+            }
+        }
+
+        if is_int::<u64>(next, radix) {
+            TokenKind::Unsigned
+        } else if is_int::<i64>(next, radix) {
+            TokenKind::Integer
+        } else if is_int::<u128>(next, radix) {
+            TokenKind::Unsigned128
+        } else if is_int::<i128>(next, radix) {
+            TokenKind::Integer128
         } else if next.parse::<f64>().is_ok() {
-            NextValue::Float
+            TokenKind::Float
         } else if next.parse::<char>().is_ok() {
-            NextValue::Char
+            TokenKind::Char
         } else {
-            NextValue::String
+            TokenKind::String
         }
     }
 }
 
-impl<'de, 'a, F> de::Deserializer<'de> for &'a mut Deserializer<'de, F>
+/// Check how the visitor describes what it expects, for types serde reads in ways
+/// that can only be told apart by that description.
+///
+/// This relies on serde_derive's wording, like `internally tagged enum Name`,
+/// which isn't part of its public api. The `internally_tagged` test breaks if that changes.
+fn expecting_starts_with<'de, V: Visitor<'de>>(visitor: &V, prefix: &str) -> bool {
+    // checks the description as it's written, without collecting it into a string
+    struct StartsWith<'a>(&'a str);
+
+    impl<'a> fmt::Write for StartsWith<'a> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let n = s.len().min(self.0.len());
+
+            if s.as_bytes()[..n] != self.0.as_bytes()[..n] {
+                return Err(fmt::Error);
+            }
+
+            self.0 = &self.0[n..];
+
+            // stop formatting once there's nothing left to match
+            if self.0.is_empty() {
+                Err(fmt::Error)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    let mut rest = StartsWith(prefix);
+    let _ = fmt::write(&mut rest, format_args!("{}", visitor as &dyn de::Expected));
+
+    rest.0.is_empty()
+}
+
+impl<'de, I> de::Deserializer<'de> for &mut Deserializer<'de, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
 {
     type Error = ScanError;
 
@@ -91,12 +574,45 @@ where
     where
         V: Visitor<'de>,
     {
-        if let Some(next) = self.peek().map(|s| NextValue::new(*s)) {
+        // serde reads internally tagged enums through `deserialize_any`
+        if expecting_starts_with(&visitor, "internally tagged enum ") {
+            // the tag is the first token, and the variant's fields are the rest of the input
+            return visitor.visit_seq(Sequence::new(&mut *self));
+        }
+
+        // a quoted phrase is a single string, even if it spans several tokens
+        let quoted = self.config.quoted_strings && self.peek().is_some_and(|s| s.starts_with('"'));
+
+        if self.top_level && !quoted {
+            let first = self.next()?;
+
+            // a single value made of several tokens can only be a sequence
+            if self.peek().is_some() {
+                let mut seq = Sequence::new(&mut *self);
+                seq.pending = Some(first);
+                return visitor.visit_seq(seq);
+            }
+
+            return self.sub_token(first).deserialize_any(visitor);
+        }
+
+        let radix = self.config.radix;
+        let kind = self.peek().cloned().map(|s| {
+            // numbers are classified without their digit separators, the same way they're read
+            match TokenKind::in_radix(&self.digits(s), radix) {
+                TokenKind::Char | TokenKind::String => TokenKind::in_radix(s, radix),
+                number => number,
+            }
+        });
+
+        if let Some(next) = kind {
             match next {
-                NextValue::Float => self.deserialize_f64(visitor),
-                NextValue::Integer => self.deserialize_i64(visitor),
-                NextValue::Unsigned => self.deserialize_u64(visitor),
-                NextValue::Char => self.deserialize_char(visitor),
+                TokenKind::Float => self.deserialize_f64(visitor),
+                TokenKind::Integer => self.deserialize_i64(visitor),
+                TokenKind::Unsigned => self.deserialize_u64(visitor),
+                TokenKind::Integer128 => self.deserialize_i128(visitor),
+                TokenKind::Unsigned128 => self.deserialize_u128(visitor),
+                TokenKind::Char => self.deserialize_char(visitor),
                 _ => self.deserialize_str(visitor),
             }
         } else {
@@ -108,91 +624,123 @@ where
     where
         V: Visitor<'de>,
     {
-        visitor.visit_bool(self.parse_next()?)
+        visitor.visit_bool(self.parse_bool()?)
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i8(self.parse_next()?)
+        visitor.visit_i8(self.parse_int()?)
     }
 
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i16(self.parse_next()?)
+        visitor.visit_i16(self.parse_int()?)
     }
 
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i32(self.parse_next()?)
+        visitor.visit_i32(self.parse_int()?)
     }
 
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_i64(self.parse_next()?)
+        visitor.visit_i64(self.parse_int()?)
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.parse_int()?)
     }
 
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u8(self.parse_next()?)
+        visitor.visit_u8(self.parse_int()?)
     }
 
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u16(self.parse_next()?)
+        visitor.visit_u16(self.parse_int()?)
     }
 
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u32(self.parse_next()?)
+        visitor.visit_u32(self.parse_int()?)
     }
 
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.parse_next()?)
+        visitor.visit_u64(self.parse_int()?)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(self.parse_int()?)
     }
 
     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f64(self.parse_next()?)
+        let f: f32 = self.parse_next()?;
+        self.check_finite(f.is_finite())?;
+        visitor.visit_f32(f)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f64(self.parse_next()?)
+        let f: f64 = self.parse_next()?;
+        self.check_finite(f.is_finite())?;
+        visitor.visit_f64(f)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_char(self.parse_next()?)
+        visitor.visit_char(self.parse_char()?)
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.next()?)
+        match self.peek().cloned() {
+            Some(s) if !self.config.quoted_strings || !s.starts_with('"') => {
+                visitor.visit_borrowed_str(self.next()?)
+            }
+            // a single quoted word can still be borrowed
+            Some(s) if s.len() >= 2 && s.ends_with('"') => {
+                self.next()?;
+                visitor.visit_borrowed_str(&s[1..s.len() - 1])
+            }
+            Some(_) => match self.parse_quoted()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            None => Err(ScanError::EOF),
+        }
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -213,6 +761,10 @@ where
     where
         V: Visitor<'de>,
     {
+        if self.config.raw_bytes {
+            return visitor.visit_byte_buf(self.next()?.as_bytes().to_vec());
+        }
+
         // treat Vec<u8> like a regular vec
         self.deserialize_seq(visitor)
     }
@@ -221,11 +773,13 @@ where
     where
         V: Visitor<'de>,
     {
-        // TODO: better option parsing
-        if self.peek().is_none() {
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
+        match self.peek().cloned() {
+            None => visitor.visit_none(),
+            Some(s) if s == self.config.none_token => {
+                self.next()?;
+                visitor.visit_none()
+            }
+            Some(_) => visitor.visit_some(self),
         }
     }
 
@@ -262,7 +816,12 @@ where
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(Sequence::new(&mut *self))
+        if self.config.counted_seqs {
+            let len = self.parse_int()?;
+            visitor.visit_seq(Sequence::new(&mut *self).with_limit(len))
+        } else {
+            visitor.visit_seq(Sequence::new(&mut *self))
+        }
     }
 
     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
@@ -288,7 +847,12 @@ where
     where
         V: Visitor<'de>,
     {
-        visitor.visit_map(Sequence::new(&mut *self))
+        if self.config.counted_maps {
+            let len = self.parse_int()?;
+            visitor.visit_map(Sequence::new(&mut *self).with_limit(len))
+        } else {
+            visitor.visit_map(Sequence::new(&mut *self))
+        }
     }
 
     fn deserialize_struct<V>(
@@ -306,13 +870,13 @@ where
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_enum(Sequence::new(&mut *self))
+        visitor.visit_enum(Sequence::new(&mut *self).with_names(variants))
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -322,36 +886,79 @@ where
         self.deserialize_str(visitor)
     }
 
-    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        // like any, not possible
-        Err(ScanError::NS("self describing formats"))
+        // an ignored value is always a single token
+        self.next()?;
+        visitor.visit_unit()
     }
 }
 
-struct Sequence<'de, 'a, F>
+/// Reads a length, then any value as a sequence of that many elements.
+struct CountedSeq<'de, 'a, I>(&'a mut Deserializer<'de, I>)
 where
     'de: 'a,
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>;
+
+impl<'de, 'a, I> de::Deserializer<'de> for CountedSeq<'de, 'a, I>
+where
+    I: Iterator<Item = &'de str>,
+{
+    type Error = ScanError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let len = self.0.parse_int()?;
+        visitor.visit_seq(Sequence::new(self.0).with_limit(len))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Sequence<'de, 'a, I>
+where
+    'de: 'a,
+    I: Iterator<Item = &'de str>,
 {
-    de: &'a mut Deserializer<'de, F>,
+    de: &'a mut Deserializer<'de, I>,
     count: usize,
     names: Option<&'a [&'static str]>,
     limit: Option<usize>,
+    /// a token that's already been read, like the value half of a `key=value` map entry
+    pending: Option<&'de str>,
+    /// the key half of the `key=value` entry whose value is pending
+    key: &'de str,
+    /// the last element didn't read any tokens, so the next one wouldn't either
+    stalled: bool,
+    /// the fields named so far by `key=value` entries, as indices into `names`
+    matched: Vec<usize>,
 }
 
-impl<'de, 'a, F> Sequence<'de, 'a, F>
+impl<'de, 'a, I> Sequence<'de, 'a, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
 {
-    fn new(de: &'a mut Deserializer<'de, F>) -> Self {
+    fn new(de: &'a mut Deserializer<'de, I>) -> Self {
+        de.top_level = false;
+        de.depth += 1;
+
         Sequence {
             de,
             count: 0,
             names: None,
             limit: None,
+            pending: None,
+            key: "",
+            stalled: false,
+            matched: Vec::new(),
         }
     }
 
@@ -368,9 +975,18 @@ where
     }
 }
 
-impl<'de, 'a, F> SeqAccess<'de> for Sequence<'de, 'a, F>
+impl<'de, 'a, I> Drop for Sequence<'de, 'a, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
+{
+    fn drop(&mut self) {
+        self.de.depth -= 1;
+    }
+}
+
+impl<'de, 'a, I> SeqAccess<'de> for Sequence<'de, 'a, I>
+where
+    I: Iterator<Item = &'de str>,
 {
     type Error = ScanError;
 
@@ -378,26 +994,37 @@ where
     where
         T: de::DeserializeSeed<'de>,
     {
-        if let Some(lim) = self.limit {
-            // if we have a limit defined, stop
-            if lim == self.count {
-                return Ok(None);
-            }
+        if let Some(token) = self.pending.take() {
+            self.count += 1;
+            return seed.deserialize(&mut self.de.sub_token(token)).map(Some);
         }
 
-        if let None = self.de.peek() {
-            // if we have no more data, stop
-            return Ok(None);
+        match self.limit {
+            // if we have a limit defined, stop
+            Some(lim) if lim == self.count => return Ok(None),
+            // otherwise the input ran out too soon
+            Some(_) => {}
+            // if we have no more data, or can't make progress, stop
+            None if self.de.peek().is_none() || self.stalled => return Ok(None),
+            None => {}
         }
 
+        let before = self.de.consumed;
         self.count += 1;
-        seed.deserialize(&mut *self.de).map(Some)
+        let value = seed.deserialize(&mut *self.de)?;
+
+        self.stalled = self.de.consumed == before;
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.limit.map(|lim| lim - self.count)
     }
 }
 
-impl<'de, 'a, F> MapAccess<'de> for Sequence<'de, 'a, F>
+impl<'de, 'a, I> MapAccess<'de> for Sequence<'de, 'a, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
 {
     type Error = ScanError;
 
@@ -409,36 +1036,96 @@ where
 
         // if we have the names, use them
         if let Some(names) = self.names {
-            if self.count >= names.len() {
+            let kv = self.de.config.kv_separator.is_some();
+
+            // unknown keys are thrown out by serde, so only keys naming a field count
+            let read = if kv { self.matched.len() } else { self.count };
+
+            if read >= names.len() || kv && self.de.peek().is_none() {
                 return Ok(None);
-            } else {
+            }
+
+            // with a separator, each entry names its field, so they can come in any order
+            if !kv {
+                // once the input runs out, skip to the last field, so serde can fill in
+                // defaults for the rest, and a trailing container can still be empty
+                if self.de.peek().is_none() {
+                    self.count = names.len() - 1;
+                }
+
                 self.count += 1;
                 return seed
                     .deserialize(names[self.count - 1].into_deserializer())
                     .map(Some);
             }
+        } else if let Some(lim) = self.limit {
+            // if we have a limit defined, stop
+            if lim == self.count {
+                return Ok(None);
+            }
+        } else if self.de.peek().is_none() {
+            // if theres nothing left, return none
+            return Ok(None);
         }
 
-        // if theres nothing left, return none
-        if let None = self.de.peek() {
-            Ok(None)
-        } else {
-            seed.deserialize(&mut *self.de).map(Some)
+        self.count += 1;
+
+        if let Some(sep) = self.de.config.kv_separator {
+            let token = self.de.next()?;
+            let (key, value) = match token.find(sep) {
+                Some(i) => (&token[..i], &token[i + sep.len_utf8()..]),
+                None => {
+                    return Err(ScanError::De {
+                        msg: format!("expected `{}` in map entry `{}`", sep, token),
+                        at_token: self.de.consumed,
+                        near: None,
+                    })
+                }
+            };
+
+            let field = self
+                .names
+                .and_then(|names| names.iter().position(|&name| name == key));
+
+            if let Some(i) = field.filter(|i| !self.matched.contains(i)) {
+                self.matched.push(i);
+            }
+
+            self.pending = Some(value);
+            self.key = key;
+            return seed.deserialize(&mut self.de.sub_token(key)).map(Some);
         }
+
+        seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
     where
         V: de::DeserializeSeed<'de>,
     {
+        if let Some(value) = self.pending.take() {
+            let consumed = self.de.consumed;
+
+            // an empty value, like `a=`, is fine for an option or a container,
+            // but anything else didn't run out of input, it was never given any
+            return match seed.deserialize(&mut self.de.sub_token(value)) {
+                Err(ScanError::EOF) if value.is_empty() => Err(ScanError::De {
+                    msg: format!("missing value for `{}`", self.key),
+                    at_token: consumed,
+                    near: None,
+                }),
+                res => res,
+            };
+        }
+
         // Deserialize a map value.
         seed.deserialize(&mut *self.de)
     }
 }
 
-impl<'de, 'a, F> EnumAccess<'de> for Sequence<'de, 'a, F>
+impl<'de, 'a, I> EnumAccess<'de> for Sequence<'de, 'a, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
 {
     type Error = ScanError;
     type Variant = Self;
@@ -447,20 +1134,37 @@ where
     where
         V: de::DeserializeSeed<'de>,
     {
+        use serde::de::IntoDeserializer;
+
+        if let (true, Some(names)) = (self.de.config.case_insensitive_variants, self.names) {
+            let token = self.de.peek().cloned().unwrap_or("");
+
+            if let Some(name) = names.iter().find(|name| name.eq_ignore_ascii_case(token)) {
+                self.de.next()?;
+                let de: de::value::StrDeserializer<ScanError> = name.into_deserializer();
+                return seed.deserialize(de).map(|v| (v, self));
+            }
+        }
+
         seed.deserialize(&mut *self.de).map(|v| (v, self))
     }
 }
 
-impl<'de, 'a, F> VariantAccess<'de> for Sequence<'de, 'a, F>
+impl<'de, 'a, I> VariantAccess<'de> for Sequence<'de, 'a, I>
 where
-    F: FnMut(char) -> bool,
+    I: Iterator<Item = &'de str>,
 {
     type Error = ScanError;
 
     // unit should be caught by EnumAccess,
-    // newtype, tuple, and struct variants not supported atm
+    // struct variants are filled in positionally, like structs
     fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
+        // only the outermost value can be checked, anything inside a sequence is followed by more tokens
+        if self.de.config.strict_unit_variants && self.de.depth == 1 {
+            self.de.end()
+        } else {
+            Ok(())
+        }
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
@@ -470,21 +1174,21 @@ where
         seed.deserialize(&mut *self.de)
     }
 
-    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    fn tuple_variant<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        de::Deserializer::deserialize_seq(self.de, visitor)
+        de::Deserializer::deserialize_tuple(&mut *self.de, len, visitor)
     }
 
     fn struct_variant<V>(
         self,
-        _fields: &'static [&'static str],
-        _visitor: V,
+        fields: &'static [&'static str],
+        visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        Err(ScanError::NS("struct enum variants"))
+        visitor.visit_map(Sequence::new(&mut *self.de).with_names(fields))
     }
 }
@@ -1,10 +1,35 @@
 //! Easily deserialize whitespace seperated data into any rust data structure supported by serde.
 //! Useful for demos, programming contests, and the like.
 //!
+//! Struct fields are read in the order they're declared. Field names, including any
+//! `#[serde(rename)]`, are never matched against the input. If the input runs out,
+//! `#[serde(default)]` fields are filled in, except for the last field, which is read
+//! from what's left, so a trailing container can be empty.
+//! Maps are read as a key followed by its value, in input order. Whether that order
+//! is kept depends on the map: a `HashMap` loses it, while a `BTreeMap` sorts by key.
+//! Strings are single tokens, so a `Vec<String>` collects the remaining words, and
+//! a phrase containing spaces has to be `"quoted"` to be read as one string.
+//! Reading into `&str` instead of `String` borrows from the input without allocating.
+//! Self-describing types, like `serde_json::Value`, read a single token as a number or a
+//! string. With `from_str_value`, they read a whole input of several tokens as a sequence of them.
+//!
 //! current issues:
-//!  * no support for enums with struct variants
-//!  * structs or tuples cannot contain an unbounded container, like a `Vec` or `HashMap`.
+//!  * structs or tuples can only contain an unbounded container, like a `Vec` or `HashMap`, as their last field.
+//!    Containers that start with their length can go anywhere, with `ScanOptions::counted_seqs`
+//!    and `from_str_counted_map`.
+//!  * internally tagged enums, with `#[serde(tag = "...")]`, read the tag from the first token and
+//!    the variant's fields from every token after it, so they have to come last. The fields are
+//!    read a token each without knowing their types, so a number can't be read into a `String`
+//!    field, and a field can't be a nested struct or container.
+//!  * `#[serde(alias)]` isn't supported, since serde lists aliases as extra fields, which throws
+//!    off the position of every field after them.
+//!  * `#[serde(flatten)]` can't read fields by position. serde reads a struct with a flattened
+//!    field as a map, without telling the deserializer its field names, so the input has to name
+//!    them, as `key value` pairs or with `ScanOptions::kv_separator`. Nested structs are already
+//!    read in place, so for positional input, leave it off.
 //!
+//! The `std` feature, on by default, adds functions for reading from stdin and other
+//! `io::Read`ers. Without it, the crate is `no_std`, and only needs `alloc`.
 //!
 //! ## Example
 //!
@@ -48,41 +73,349 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
 extern crate serde;
+extern crate serde_scan_macros;
 
+#[cfg(test)]
+extern crate rust_decimal;
+#[cfg(test)]
+extern crate serde_bytes;
 #[cfg(test)]
 #[cfg_attr(test, macro_use)]
 extern crate serde_derive;
+#[cfg(test)]
+extern crate serde_json;
+// the test harness needs std either way
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
 
 mod de;
+mod options;
+mod pattern;
+#[cfg(feature = "std")]
+mod read;
+mod records;
+mod scanner;
+
+/// the parts of `std` used outside of reading io, which `core` and `alloc` provide without it
+mod lib {
+    #[cfg(not(feature = "std"))]
+    pub use alloc::{borrow, string, vec};
+    #[cfg(not(feature = "std"))]
+    pub use core::{any, array, error, fmt, iter, num, option, str};
+
+    #[cfg(feature = "std")]
+    pub use std::{any, array, borrow, error, fmt, iter, num, option, str, string, vec};
+}
 
 mod errors {
+    use lib::error::Error;
+    use lib::fmt::{self, Display};
+    use lib::str::Utf8Error;
+    use lib::string::{String, ToString};
     use serde::de;
-    use std::error::Error;
-    use std::fmt::{self, Display};
+    #[cfg(feature = "std")]
     use std::io;
 
     // TODO: make this better
 
     #[derive(Debug)]
     pub enum ScanError {
+        #[cfg(feature = "std")]
         Io(io::Error),
-        De,
+        Utf8(Utf8Error),
+        De {
+            msg: String,
+            at_token: usize,
+            near: Option<String>,
+        },
+        ParseFailed {
+            token: String,
+            target: &'static str,
+            at_token: usize,
+            near: Option<String>,
+        },
+        OutOfRange {
+            token: String,
+            target: &'static str,
+            at_token: usize,
+            near: Option<String>,
+        },
+        TrailingTokens {
+            token: String,
+            at_token: usize,
+            near: Option<String>,
+        },
+        UnknownVariant {
+            variant: String,
+            expected: &'static [&'static str],
+            at_token: usize,
+            near: Option<String>,
+        },
+        PatternMismatch {
+            expected: char,
+            found: char,
+            offset: usize,
+        },
         EOF,
         NS(&'static str),
     }
 
+    /// io errors can't be cloned, so the clone keeps just their kind and message.
+    impl Clone for ScanError {
+        fn clone(&self) -> Self {
+            match *self {
+                #[cfg(feature = "std")]
+                ScanError::Io(ref e) => ScanError::Io(io::Error::new(e.kind(), e.to_string())),
+                ScanError::Utf8(e) => ScanError::Utf8(e),
+                ScanError::De {
+                    ref msg,
+                    at_token,
+                    ref near,
+                } => ScanError::De {
+                    msg: msg.clone(),
+                    at_token,
+                    near: near.clone(),
+                },
+                ScanError::ParseFailed {
+                    ref token,
+                    target,
+                    at_token,
+                    ref near,
+                } => ScanError::ParseFailed {
+                    token: token.clone(),
+                    target,
+                    at_token,
+                    near: near.clone(),
+                },
+                ScanError::OutOfRange {
+                    ref token,
+                    target,
+                    at_token,
+                    ref near,
+                } => ScanError::OutOfRange {
+                    token: token.clone(),
+                    target,
+                    at_token,
+                    near: near.clone(),
+                },
+                ScanError::TrailingTokens {
+                    ref token,
+                    at_token,
+                    ref near,
+                } => ScanError::TrailingTokens {
+                    token: token.clone(),
+                    at_token,
+                    near: near.clone(),
+                },
+                ScanError::UnknownVariant {
+                    ref variant,
+                    expected,
+                    at_token,
+                    ref near,
+                } => ScanError::UnknownVariant {
+                    variant: variant.clone(),
+                    expected,
+                    at_token,
+                    near: near.clone(),
+                },
+                ScanError::PatternMismatch {
+                    expected,
+                    found,
+                    offset,
+                } => ScanError::PatternMismatch {
+                    expected,
+                    found,
+                    offset,
+                },
+                ScanError::EOF => ScanError::EOF,
+                ScanError::NS(val) => ScanError::NS(val),
+            }
+        }
+    }
+
+    /// io errors can't be compared either, so they're equal when their kinds are.
+    impl PartialEq for ScanError {
+        fn eq(&self, other: &Self) -> bool {
+            use self::ScanError::*;
+
+            match (self, other) {
+                #[cfg(feature = "std")]
+                (Io(a), Io(b)) => a.kind() == b.kind(),
+                (Utf8(a), Utf8(b)) => a == b,
+                (
+                    De {
+                        msg: a,
+                        at_token: i,
+                        near: m,
+                    },
+                    De {
+                        msg: b,
+                        at_token: j,
+                        near: n,
+                    },
+                ) => a == b && i == j && m == n,
+                (
+                    ParseFailed {
+                        token: a,
+                        target: t,
+                        at_token: i,
+                        near: m,
+                    },
+                    ParseFailed {
+                        token: b,
+                        target: u,
+                        at_token: j,
+                        near: n,
+                    },
+                )
+                | (
+                    OutOfRange {
+                        token: a,
+                        target: t,
+                        at_token: i,
+                        near: m,
+                    },
+                    OutOfRange {
+                        token: b,
+                        target: u,
+                        at_token: j,
+                        near: n,
+                    },
+                ) => a == b && t == u && i == j && m == n,
+                (
+                    TrailingTokens {
+                        token: a,
+                        at_token: i,
+                        near: m,
+                    },
+                    TrailingTokens {
+                        token: b,
+                        at_token: j,
+                        near: n,
+                    },
+                ) => a == b && i == j && m == n,
+                (
+                    UnknownVariant {
+                        variant: a,
+                        expected: e,
+                        at_token: i,
+                        near: m,
+                    },
+                    UnknownVariant {
+                        variant: b,
+                        expected: f,
+                        at_token: j,
+                        near: n,
+                    },
+                ) => a == b && e == f && i == j && m == n,
+                (
+                    PatternMismatch {
+                        expected: a,
+                        found: c,
+                        offset: i,
+                    },
+                    PatternMismatch {
+                        expected: b,
+                        found: d,
+                        offset: j,
+                    },
+                ) => a == b && c == d && i == j,
+                (EOF, EOF) => true,
+                (NS(a), NS(b)) => a == b,
+                _ => false,
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
     impl From<io::Error> for ScanError {
         fn from(e: io::Error) -> Self {
             ScanError::Io(e)
         }
     }
 
+    impl From<Utf8Error> for ScanError {
+        fn from(e: Utf8Error) -> Self {
+            ScanError::Utf8(e)
+        }
+    }
+
     impl Display for ScanError {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.fmt_message(f)?;
+
+            match self.near() {
+                Some(near) => write!(f, " near `{}`", near),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl ScanError {
+        fn fmt_message(&self, f: &mut fmt::Formatter) -> fmt::Result {
             match *self {
+                #[cfg(feature = "std")]
                 ScanError::Io(ref e) => write!(f, "io: {}", e),
-                ScanError::De => write!(f, "deserialization error"),
+                ScanError::Utf8(ref e) => write!(f, "utf8: {}", e),
+                ScanError::De {
+                    ref msg, at_token, ..
+                } => write!(f, "{} at token {}", msg, at_token),
+                ScanError::ParseFailed {
+                    ref token,
+                    target,
+                    at_token,
+                    ..
+                } => write!(
+                    f,
+                    "could not parse `{}` as {} at token {}",
+                    token, target, at_token
+                ),
+                ScanError::OutOfRange {
+                    ref token,
+                    target,
+                    at_token,
+                    ..
+                } => write!(
+                    f,
+                    "value `{}` out of range for {} at token {}",
+                    token, target, at_token
+                ),
+                ScanError::TrailingTokens {
+                    ref token,
+                    at_token,
+                    ..
+                } => write!(
+                    f,
+                    "unexpected trailing input `{}` at token {}",
+                    token, at_token
+                ),
+                ScanError::UnknownVariant {
+                    ref variant,
+                    expected,
+                    at_token,
+                    ..
+                } => write!(
+                    f,
+                    "unknown variant `{}`, expected one of `{}` at token {}",
+                    variant,
+                    expected.join("`, `"),
+                    at_token
+                ),
+                ScanError::PatternMismatch {
+                    expected,
+                    found,
+                    offset,
+                } => write!(
+                    f,
+                    "input doesn't match pattern at byte {}: expected `{}`, found `{}`",
+                    offset, expected, found
+                ),
                 ScanError::EOF => write!(f, "unexpected end of input"),
                 ScanError::NS(val) => {
                     write!(f, "deseralizing `{}` is not supported at this time.", val)
@@ -91,245 +424,2453 @@ mod errors {
         }
     }
 
-    impl Error for ScanError {}
+    impl ScanError {
+        /// Returns `true` if the input ran out before a value could be parsed.
+        pub fn is_eof(&self) -> bool {
+            matches!(*self, ScanError::EOF)
+        }
+
+        /// Returns `true` if the input was present, but could not be parsed.
+        pub fn is_parse_error(&self) -> bool {
+            matches!(
+                *self,
+                ScanError::De { .. }
+                    | ScanError::ParseFailed { .. }
+                    | ScanError::OutOfRange { .. }
+                    | ScanError::TrailingTokens { .. }
+                    | ScanError::UnknownVariant { .. }
+            )
+        }
+
+        /// The input around the token that failed, for parse errors read with
+        /// `ScanOptions::verbose_errors`.
+        pub fn near(&self) -> Option<&str> {
+            match *self {
+                ScanError::De { ref near, .. }
+                | ScanError::ParseFailed { ref near, .. }
+                | ScanError::OutOfRange { ref near, .. }
+                | ScanError::TrailingTokens { ref near, .. }
+                | ScanError::UnknownVariant { ref near, .. } => near.as_deref(),
+                _ => None,
+            }
+        }
+
+        /// record where in the token stream this error happened, for errors
+        /// raised from outside the deserializer (like `de::Error::custom`)
+        pub(crate) fn at_token(self, consumed: usize) -> Self {
+            match self {
+                ScanError::De { msg, near, .. } => ScanError::De {
+                    msg,
+                    at_token: consumed,
+                    near,
+                },
+                ScanError::UnknownVariant {
+                    variant,
+                    expected,
+                    near,
+                    ..
+                } => ScanError::UnknownVariant {
+                    variant,
+                    expected,
+                    at_token: consumed,
+                    near,
+                },
+                e => e,
+            }
+        }
+
+        /// attach the input around the failing token to a parse error,
+        /// keeping whatever was attached first
+        pub(crate) fn with_near(mut self, input: &str) -> Self {
+            match self {
+                ScanError::De { ref mut near, .. }
+                | ScanError::ParseFailed { ref mut near, .. }
+                | ScanError::OutOfRange { ref mut near, .. }
+                | ScanError::TrailingTokens { ref mut near, .. }
+                | ScanError::UnknownVariant { ref mut near, .. }
+                    if near.is_none() && !input.is_empty() =>
+                {
+                    *near = Some(input.to_string());
+                }
+                _ => {}
+            }
+            self
+        }
+    }
+
+    impl Error for ScanError {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            match *self {
+                #[cfg(feature = "std")]
+                ScanError::Io(ref e) => Some(e),
+                ScanError::Utf8(ref e) => Some(e),
+                _ => None,
+            }
+        }
+    }
 
     impl de::Error for ScanError {
-        fn custom<T: Display>(_msg: T) -> Self {
-            ScanError::De
+        fn custom<T: Display>(msg: T) -> Self {
+            ScanError::De {
+                msg: msg.to_string(),
+                at_token: 0,
+                near: None,
+            }
+        }
+
+        // fields are only missing when we run out of input
+        fn missing_field(_field: &'static str) -> Self {
+            ScanError::EOF
+        }
+
+        fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+            ScanError::UnknownVariant {
+                variant: variant.to_string(),
+                expected,
+                at_token: 0,
+                near: None,
+            }
         }
     }
 }
 
+pub use de::TokenKind;
 pub use errors::ScanError;
+pub use options::ScanOptions;
+pub use pattern::Pattern;
+#[cfg(feature = "std")]
+pub use read::{from_reader, lines, next_line, next_line_buf, next_n_lines, next_record};
+pub use records::RecordDeserializer;
+pub use scanner::{Checkpoint, Scanner};
 
+use lib::vec::Vec;
 use serde::de::{Deserialize, DeserializeOwned};
 
-/// Get a line of input from stdin, and parse it.
+/// Parse a string contaning whitespace seperated data.
+///
+pub fn from_str<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
+    de::Deserializer::from_str(s).deserialize()
+}
+
+/// Parse a string contaning whitespace seperated data, making sure every token is used.
 ///
-/// Extra data not needed for parsing `T` is thrown out.
+/// Returns `ScanError::TrailingTokens` if there is input left over after parsing `T`.
 ///
-pub fn next_line<T: DeserializeOwned>() -> Result<T, ScanError> {
-    use std::io;
+pub fn from_str_exact<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
+    let mut de = de::Deserializer::from_str(s);
+    let value = de.deserialize()?;
 
-    let input = io::stdin();
-    let mut buf = String::new();
+    de.end().map(|_| value)
+}
 
-    input.read_line(&mut buf)?;
+/// Parse a whole string as one self-describing value, like a `serde_json::Value`.
+///
+/// An input of several tokens is read as a sequence of them, while a single token
+/// is read as a number or a string, the same as with `from_str`.
+///
+/// ```rust
+/// # extern crate serde_json;
+/// # extern crate serde_scan;
+/// # fn main() {
+/// let v: serde_json::Value = serde_scan::from_str_value("1 two 3.0").unwrap();
+///
+/// assert_eq!(v, serde_json::json!([1, "two", 3.0]));
+/// # }
+/// ```
+///
+pub fn from_str_value<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
+    let mut de = de::Deserializer::from_str(s).whole_input();
+    let value = de.deserialize()?;
 
-    from_str(&buf)
+    de.end().map(|_| value)
 }
 
-/// Parse a string contaning whitespace seperated data.
+/// Parse a string contaning whitespace seperated data, returning the value along with
+/// the rest of the input after the last token used.
 ///
-pub fn from_str<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
-    let mut de = de::Deserializer::<fn(char) -> bool>::from_str(s);
+/// Handy for parsing the start of a string, and handing the rest off to something else.
+///
+/// ```rust
+/// let (n, rest): (u32, &str) = serde_scan::from_str_consuming("3 rest of input").unwrap();
+///
+/// assert_eq!(n, 3);
+/// assert_eq!(rest, " rest of input");
+/// ```
+///
+pub fn from_str_consuming<'a, T: Deserialize<'a>>(s: &'a str) -> Result<(T, &'a str), ScanError> {
+    let mut de = de::Deserializer::from_str(s);
+    let value = de.deserialize()?;
 
-    T::deserialize(&mut de)
+    Ok((value, de.remainder()))
 }
 
-/// Parse a string contaning data seperated by whitespace or any character in the given skip string.
+/// Parse a byte slice contaning whitespace seperated, utf-8 encoded data.
 ///
-pub fn from_str_skipping<'a, T: Deserialize<'a>>(set: &'a str, s: &'a str) -> Result<T, ScanError> {
-    from_closure(|ch| ch.is_whitespace() || set.contains(ch), s)
+pub fn from_bytes<'a, T: Deserialize<'a>>(b: &'a [u8]) -> Result<T, ScanError> {
+    from_str(lib::str::from_utf8(b)?)
 }
 
-#[doc(hidden)]
-pub fn from_closure<'a, F, T>(f: F, s: &'a str) -> Result<T, ScanError>
-where
-    T: Deserialize<'a>,
-    F: FnMut(char) -> bool,
-{
-    let mut de = de::Deserializer::from_closure(f, s);
+/// Parse a string contaning whitespace seperated data into a sequence of values.
+///
+/// Values are parsed one after the other until the input runs out. Iteration stops
+/// after the first error.
+///
+pub fn from_str_iter<'a, T: Deserialize<'a>>(
+    s: &'a str,
+) -> impl Iterator<Item = Result<T, ScanError>> + 'a {
+    let mut de = de::Deserializer::from_str(s);
+    let mut failed = false;
 
-    T::deserialize(&mut de)
+    lib::iter::from_fn(move || {
+        if failed || de.is_empty() {
+            return None;
+        }
+
+        let next = de.deserialize();
+        failed = next.is_err();
+        Some(next)
+    })
 }
 
-/// The `scan!` macro.
+/// Parse each line of a string into a value, skipping lines that start with an unknown enum variant.
 ///
-/// Useful for extracting important bits from simple ad-hoc text files.
+/// Useful for skipping comments or unsupported commands. Other errors, like a bad value
+/// after a known variant, are still returned. Blank lines are skipped.
+///
+/// ```rust
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate serde_scan;
+/// # fn main() {
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// #[serde(rename_all = "snake_case")]
+/// enum Cmd {
+///     Push(u32),
+///     Pop,
+/// }
+///
+/// let input = "push 1\n; a comment\npop\n";
+/// let cmds: Vec<Cmd> = serde_scan::from_str_iter_lenient(input)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(cmds, [Cmd::Push(1), Cmd::Pop]);
+/// # }
+/// ```
+///
+pub fn from_str_iter_lenient<'a, T: Deserialize<'a>>(
+    s: &'a str,
+) -> impl Iterator<Item = Result<T, ScanError>> + 'a {
+    s.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| from_str(line))
+        .filter(|res| !matches!(res, Err(ScanError::UnknownVariant { at_token: 1, .. })))
+}
+
+/// Parse a string contaning whitespace seperated data, where every map starts with its length.
+///
+/// The map ends after that many key-value pairs, so it doesn't have to be the last thing in the input.
 ///
 /// # Example
 ///
-/// ```rust,no_run
-/// # use serde_scan::scan;
-/// # use serde_scan::ScanError;
+/// ```rust
+/// # use std::collections::HashMap;
+/// let (map, rest): (HashMap<char, u32>, &str) =
+///     serde_scan::from_str_counted_map("2 a 1 b 2 rest").unwrap();
 ///
-/// # fn main() -> Result<(), ScanError> {
-/// let line = "#1 @ 555,891: 18x12";
-/// let parsed = scan!("#{} @ {},{}: {}x{}" <- line)?;
-/// # Ok(()) }
+/// assert_eq!(map[&'b'], 2);
+/// assert_eq!(rest, "rest");
 /// ```
 ///
-#[macro_export]
-macro_rules! scan {
-    ($scan_string:tt <- $input:ident) => {{
-        let mut chaff = $scan_string.split("{}").flat_map(|s| s.chars()).peekable();
-
-        $crate::from_closure(
-            move |next_ch| {
-                if let Some(&ch) = chaff.peek() {
-                    if next_ch == ch || ch.is_whitespace() && next_ch.is_whitespace() {
-                        chaff.next();
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            },
-            $input,
-        )
-    }};
-    ($($t:tt)*) => {
-        compile_error!("invalid format.\nusage: scan!(\"scan literal\" <- value)");
+pub fn from_str_counted_map<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
+    let config = de::Config {
+        counted_maps: true,
+        ..de::Config::default()
     };
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn numbers() {
-        let a: u64 = from_str("64").unwrap();
-        let b: i64 = from_str("-64").unwrap();
+    de::Deserializer::from_str(s)
+        .with_config(config)
+        .deserialize()
+}
 
-        assert_eq!(a, 64);
-        assert_eq!(b, -64);
-    }
+/// Parse a string contaning whitespace seperated data, where every integer is in the given radix.
+///
+/// Integers don't need a prefix, so `ff` is 255 in radix 16.
+///
+/// # Panics
+///
+/// Panics if `radix` is not in the range from 2 to 36.
+///
+pub fn from_str_with_radix<'a, T: Deserialize<'a>>(radix: u32, s: &'a str) -> Result<T, ScanError> {
+    ScanOptions::new().radix(radix).from_str(s)
+}
 
-    #[test]
-    fn tuples() {
-        let a: (f32,) = from_str("  45.34 ").unwrap();
-        let b: (u8, u8) = from_str("   3 4   ").unwrap();
-        let c: (u32, String, u32) = from_str(" 413 plus 612 ").unwrap();
+/// Like [`from_grid`](fn.from_grid.html), but for grids stored one column after another.
+///
+/// The result is still indexed by row first, so `grid[r][c]` is row `r`, column `c`.
+///
+/// ```rust
+/// let grid: Vec<Vec<u32>> = serde_scan::from_grid_colmajor("1 4\n2 5\n3 6", 2, 3).unwrap();
+/// assert_eq!(grid, [[1, 2, 3], [4, 5, 6]]);
+/// ```
+///
+pub fn from_grid_colmajor<T: DeserializeOwned>(
+    s: &str,
+    rows: usize,
+    cols: usize,
+) -> Result<Vec<Vec<T>>, ScanError> {
+    let columns: Vec<Vec<T>> = from_grid(s, cols, rows)?;
+    let mut grid: Vec<Vec<T>> = (0..rows).map(|_| Vec::with_capacity(cols)).collect();
 
-        assert_eq!(a.0, 45.34);
-        assert_eq!(b, (3, 4));
-        assert_eq!(c, (413, String::from("plus"), 612));
+    for column in columns {
+        for (row, value) in grid.iter_mut().zip(column) {
+            row.push(value);
+        }
     }
 
-    #[test]
-    fn strings() {
-        let a: (String, &str) = from_str("a a").unwrap();
-        let b: (String, &[u8]) = from_str("b b").unwrap();
+    Ok(grid)
+}
 
-        assert_eq!(a.0.as_str(), a.1);
-        assert_eq!(b.0.as_bytes(), b.1);
-    }
+/// Parse whitespace seperated values into an existing slice, without allocating.
+///
+/// Stops when either the slice is full or the input runs out, and returns how many
+/// values were read. Anything after the last value is left alone.
+///
+/// ```rust
+/// let mut buf = [0u32; 4];
+///
+/// let n = serde_scan::fill_from_str("1 2 3", &mut buf).unwrap();
+/// assert_eq!(&buf[..n], [1, 2, 3]);
+/// ```
+///
+pub fn fill_from_str<T: DeserializeOwned>(s: &str, out: &mut [T]) -> Result<usize, ScanError> {
+    let mut de = de::Deserializer::from_str(s);
+    let mut filled = 0;
 
-    #[test]
-    fn options() {
-        let a: Result<u32, ScanError> = from_str("    ");
-        let b: Option<u32> = from_str("   ").unwrap();
-        let c: Option<u32> = from_str(" 7 ").unwrap();
+    for slot in out.iter_mut() {
+        if de.is_empty() {
+            break;
+        }
 
-        assert!(a.is_err());
-        assert_eq!(b, None);
-        assert_eq!(c, Some(7));
+        *slot = de.deserialize()?;
+        filled += 1;
     }
 
-    #[test]
-    fn three_ways() {
-        #[derive(Deserialize, Debug, PartialEq)]
-        struct Triple {
-            a: u32,
-            b: u32,
-            c: u32,
-        }
+    Ok(filled)
+}
 
-        let s = r#" 1 
-                2 
-        3 "#;
+/// Parse a fixed number of values, filling in any missing at the end with their default.
+///
+/// Useful when the last row of a grid may be short. Anything after the last value is left alone.
+///
+/// ```rust
+/// let row: [u32; 5] = serde_scan::from_str_padded("1 2 3").unwrap();
+/// assert_eq!(row, [1, 2, 3, 0, 0]);
+/// ```
+///
+pub fn from_str_padded<T, const N: usize>(s: &str) -> Result<[T; N], ScanError>
+where
+    T: Default + DeserializeOwned,
+{
+    let mut out = lib::array::from_fn(|_| T::default());
+    fill_from_str(s, &mut out)?;
 
-        let a: [u32; 3] = from_str(s).unwrap();
-        assert_eq!(a, [1, 2, 3]);
+    Ok(out)
+}
 
-        let b: (u32, u32, u32) = from_str(s).unwrap();
-        assert_eq!(b, (1, 2, 3));
+/// Parse a string of flags, like `101101`, with one `bool` for every character.
+///
+/// Whitespace is skipped, so a grid of flags can be read in one go. Each character is read
+/// like a `bool` token, so `1`/`0`, `t`/`f` and `y`/`n` all work.
+///
+/// ```rust
+/// let flags = serde_scan::from_bitstring("101\n011").unwrap();
+/// assert_eq!(flags, [true, false, true, false, true, true]);
+/// ```
+///
+pub fn from_bitstring(s: &str) -> Result<Vec<bool>, ScanError> {
+    let chars = s
+        .char_indices()
+        .filter(|&(_, ch)| !ch.is_whitespace())
+        .map(|(i, ch)| &s[i..i + ch.len_utf8()]);
 
-        let c: Triple = from_str(s).unwrap();
-        assert_eq!(c, Triple { a: 1, b: 2, c: 3 });
-    }
+    from_tokens(chars)
+}
 
-    #[test]
-    fn enums() {
-        let color_list = r#"
-            red
-            blue
-            green
-            green
-            red
-            blue
-        "#;
+/// Parse a string contaning whitespace seperated data, where every integer is a fixed point
+/// decimal with `scale` digits after the decimal point.
+///
+/// Handy for reading prices as a whole number of cents. Having more digits after the
+/// decimal point than `scale` is an error.
+///
+/// ```rust
+/// let cents: Vec<u64> = serde_scan::from_str_scaled(2, "12.34 5 0.5").unwrap();
+/// assert_eq!(cents, [1234, 500, 50]);
+/// ```
+///
+pub fn from_str_scaled<'a, T: Deserialize<'a>>(scale: u32, s: &'a str) -> Result<T, ScanError> {
+    ScanOptions::new().decimal_scale(scale).from_str(s)
+}
 
-        #[derive(Deserialize, Debug, PartialEq)]
-        #[serde(rename_all = "snake_case")]
-        enum Color {
-            Red,
-            Blue,
-            Green,
-        }
+/// Parse a string contaning a sequence of whitespace seperated values,
+/// where the first token is the number of values.
+///
+/// Anything after the last value is left alone, so unlike `from_str`, it's fine
+/// for there to be more data after the sequence.
+///
+/// ```rust
+/// let v: Vec<u32> = serde_scan::from_str_seq_prefixed("3 10 20 30 99").unwrap();
+/// assert_eq!(v, [10, 20, 30]);
+/// ```
+///
+pub fn from_str_seq_prefixed<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, ScanError> {
+    de::Deserializer::from_str(s).deserialize_counted()
+}
 
-        let colors: Vec<Color> = from_str(color_list).unwrap();
+/// Parse whitespace seperated values until one equals `sentinel`, for lists that end with
+/// a marker like `0` or `-1` instead of starting with their length.
+///
+/// The sentinel is read but not included, and anything after it is left alone.
+/// Returns `ScanError::EOF` if the input runs out before the sentinel.
+///
+/// ```rust
+/// let v: Vec<i32> = serde_scan::from_str_until(0, "3 1 4 0 9").unwrap();
+/// assert_eq!(v, [3, 1, 4]);
+/// ```
+///
+pub fn from_str_until<T>(sentinel: T, s: &str) -> Result<Vec<T>, ScanError>
+where
+    T: DeserializeOwned + PartialEq,
+{
+    let mut de = de::Deserializer::from_str(s);
+    let mut out = Vec::new();
 
-        assert_eq!(colors.len(), 6);
-        assert_eq!(colors[3], Color::Green);
-    }
+    loop {
+        let value: T = de.deserialize()?;
 
-    #[test]
-    fn enum_tuple() {
-        #[derive(Deserialize, Debug, PartialEq)]
-        #[serde(rename_all = "snake_case")]
-        enum EnumTuple {
-            Variant(i32),
-            Tuple(String, String, usize),
+        if value == sentinel {
+            return Ok(out);
         }
 
-        // this might work in the future
-        let a: EnumTuple = from_str("variant 1").unwrap();
-        let b: EnumTuple = from_str("tuple two three 4").unwrap();
-
-        assert_eq!(a, EnumTuple::Variant(1));
-        assert_eq!(
-            b,
-            EnumTuple::Tuple("two".to_string(), "three".to_string(), 4)
-        );
+        out.push(value);
     }
+}
 
-    #[test]
-    fn byte_bufs() {
-        // maybe: add support for 0x, 0o, 0b
-        let bytes: Vec<u8> = from_str("0 1 2 255").unwrap();
-        assert_eq!(bytes[0], 0x00);
-        assert_eq!(bytes.len(), 4);
+/// Parse a string contaning a grid of whitespace seperated values, stored one row after another.
+///
+/// Reads `rows * cols` values, so the grid doesn't need to be split into lines.
+/// Anything after the last value is left alone.
+///
+/// ```rust
+/// let grid: Vec<Vec<u32>> = serde_scan::from_grid("1 2 3\n4 5 6", 2, 3).unwrap();
+/// assert_eq!(grid, [[1, 2, 3], [4, 5, 6]]);
+/// ```
+///
+pub fn from_grid<T: DeserializeOwned>(
+    s: &str,
+    rows: usize,
+    cols: usize,
+) -> Result<Vec<Vec<T>>, ScanError> {
+    let mut de = de::Deserializer::from_str(s);
 
-        let byte_str: &[u8] = from_str("0x32323").unwrap();
-        assert_eq!(byte_str, b"0x32323");
-    }
+    (0..rows)
+        .map(|_| (0..cols).map(|_| de.deserialize()).collect())
+        .collect()
+}
 
-    #[test]
-    fn unsupported() {
-        #[derive(Deserialize, Debug, PartialEq)]
+/// Parse a string contaning data seperated by whitespace or any character in the given skip string.
+///
+/// For more control over which characters are skipped, see [`ScanOptions`](struct.ScanOptions.html).
+///
+pub fn from_str_skipping<'a, T: Deserialize<'a>>(set: &str, s: &'a str) -> Result<T, ScanError> {
+    ScanOptions::new().also_skip(set).from_str(s)
+}
+
+/// Like [`from_str_skipping`](fn.from_str_skipping.html), but takes ownership of the skip set.
+///
+/// Useful when the skip set is built at runtime.
+///
+/// ```rust
+/// let delims: Vec<char> = vec![',', ';'];
+///
+/// let row: Vec<u32> = serde_scan::from_str_skipping_owned(delims, "1,2;3 4").unwrap();
+/// assert_eq!(row, vec![1, 2, 3, 4]);
+/// ```
+///
+pub fn from_str_skipping_owned<'a, S, T>(set: S, s: &'a str) -> Result<T, ScanError>
+where
+    S: IntoIterator<Item = char>,
+    T: Deserialize<'a>,
+{
+    let set: Vec<char> = set.into_iter().collect();
+
+    from_closure(move |ch| ch.is_whitespace() || set.contains(&ch), s)
+}
+
+/// Split a string into tokens, the same way `from_str` does.
+///
+/// Handy for seeing exactly what the parser sees when parsing fails.
+///
+/// ```rust
+/// let tokens: Vec<&str> = serde_scan::tokens("  move\t3 \n left ").collect();
+/// assert_eq!(tokens, ["move", "3", "left"]);
+/// ```
+///
+pub fn tokens(s: &str) -> impl Iterator<Item = &str> {
+    de::str_tokens(s)
+}
+
+/// Check what the first whitespace seperated token of a string looks like,
+/// without parsing it.
+///
+/// Returns `None` if there are no tokens.
+///
+/// ```rust
+/// use serde_scan::TokenKind;
+///
+/// assert_eq!(serde_scan::peek_kind("42 apples"), Some(TokenKind::Unsigned));
+/// assert_eq!(serde_scan::peek_kind("apples"), Some(TokenKind::String));
+/// ```
+///
+pub fn peek_kind(s: &str) -> Option<TokenKind> {
+    tokens(s).next().map(TokenKind::new)
+}
+
+/// Parse tokens that have already been split up.
+///
+/// Each item is one token, so it can contain whitespace.
+///
+/// ```rust
+/// let tokens = "1,2,3".split(',');
+/// let v: (u32, u32, u32) = serde_scan::from_tokens(tokens).unwrap();
+///
+/// assert_eq!(v, (1, 2, 3));
+/// ```
+///
+pub fn from_tokens<'a, I, T>(tokens: I) -> Result<T, ScanError>
+where
+    I: IntoIterator<Item = &'a str>,
+    T: Deserialize<'a>,
+{
+    de::Deserializer::from_tokens(tokens.into_iter()).deserialize()
+}
+
+/// Adds a `scan` method to strings, as a shorthand for [`from_str`](fn.from_str.html).
+///
+/// Named `scan` rather than `parse`, so it doesn't get mixed up with `str::parse`.
+///
+/// ```rust
+/// use serde_scan::ScanStr;
+///
+/// let v = "1 2 3".scan::<[u32; 3]>().unwrap();
+/// assert_eq!(v, [1, 2, 3]);
+/// ```
+///
+pub trait ScanStr {
+    /// Parse this string as whitespace seperated data.
+    fn scan<'a, T: Deserialize<'a>>(&'a self) -> Result<T, ScanError>;
+}
+
+impl ScanStr for str {
+    fn scan<'a, T: Deserialize<'a>>(&'a self) -> Result<T, ScanError> {
+        from_str(self)
+    }
+}
+
+#[doc(hidden)]
+pub fn from_closure<'a, F, T>(f: F, s: &'a str) -> Result<T, ScanError>
+where
+    T: Deserialize<'a>,
+    F: FnMut(char) -> bool,
+{
+    let mut de = de::Deserializer::from_closure(f, s);
+
+    de.deserialize()
+}
+
+#[doc(hidden)]
+pub use pattern::from_pattern as __from_pattern;
+#[doc(hidden)]
+pub use pattern::from_pattern_exact as __from_pattern_exact;
+#[doc(hidden)]
+pub use serde::de::IgnoredAny as __IgnoredAny;
+#[doc(hidden)]
+pub use serde_scan_macros::scan_fields as __scan_fields;
+#[doc(hidden)]
+pub use serde_scan_macros::scan_select as __scan_select;
+#[doc(hidden)]
+pub use serde_scan_macros::scan_type as __scan_type;
+#[doc(hidden)]
+pub use serde_scan_macros::scan_untyped as __scan_untyped;
+
+/// The `scan!` macro.
+///
+/// Useful for extracting important bits from simple ad-hoc text files.
+///
+/// Placeholders can name the type they capture, like `{u32}`. Untyped `{}` placeholders
+/// are inferred from how the result is used. A `{_}` placeholder matches a value
+/// but leaves it out of the result. Literal braces are written `{{` and `}}`, like in `format!`.
+///
+/// A `{*}` placeholder at the end of the pattern captures the rest of the line as a `&str`,
+/// spaces and all.
+///
+/// A list of patterns, like `scan!(["{} -> {}", "{} to {}"] <- line)`, tries each one in turn
+/// and returns the first match, or the last error.
+///
+/// Prefixing the pattern with a type, like `scan!(Claim; "..." <- line)`, parses the captures
+/// straight into that type, filling its fields in order. The number of captures is checked
+/// when parsing, not at compile time, since the macro can't see the type's fields, and a field
+/// like a tuple can take several captures. Captures left over after every field is filled are a
+/// `ScanError::TrailingTokens`, and running out before then is a `ScanError::EOF`.
+/// Every capture fills a field, so `{_}` and `{Type}` placeholders are a compile error here:
+///
+/// ```rust,compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # #[macro_use] extern crate serde_scan;
+/// # fn main() {
+/// #[derive(Deserialize)]
+/// struct Pair {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// let pair = scan!(Pair; "{} {_} {}" <- "1 junk 2");
+/// # }
+/// ```
+///
+/// Listing the fields too, like `scan!(Claim { id, x, y }; "..." <- line)`, fills each field
+/// from one capture, in order, and checks at compile time that there's a capture for every
+/// field. The captures are read as the fields' types, so the struct doesn't need to implement
+/// `Deserialize`, and `{_}` and `{Type}` placeholders work as usual.
+///
+/// ```rust,compile_fail
+/// # #[macro_use] extern crate serde_scan;
+/// # fn main() {
+/// struct Pair {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// // error: `Pair` is given 2 fields, but the pattern has 3 captures
+/// let pair = scan!(Pair { a, b }; "{} {} {}" <- "1 2 3");
+/// # }
+/// ```
+///
+/// Starting with `let`, like `scan!(let (a, b) = "{u32} {u32}" <- line)`, binds the captures
+/// directly, and returns early with `?` if the line doesn't match.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # #[macro_use] extern crate serde_derive;
+/// # use serde_scan::scan;
+/// # use serde_scan::ScanError;
+///
+/// # fn main() -> Result<(), ScanError> {
+/// let line = "#1 @ 555,891: 18x12";
+/// let parsed: (u32, u32, u32, u32, u32) = scan!("#{} @ {},{}: {}x{}" <- line)?;
+///
+/// let (id, name) = scan!("{u32}: {String}" <- "7: seven")?;
+///
+/// #[derive(Deserialize)]
+/// struct Claim {
+///     id: u32,
+///     start: (u32, u32),
+///     dim: (u32, u32),
+/// }
+///
+/// let line = "#1 @ 555,891: 18x12";
+/// let claim = scan!(Claim; "#{} @ {},{}: {}x{}" <- line)?;
+///
+/// scan!(let (x, y) = "{i32},{i32}" <- "3,-4");
+/// scan!(let name = "hello, {&str}!" <- "hello, world!");
+/// # Ok(()) }
+/// ```
+///
+#[macro_export]
+macro_rules! scan {
+    (let $binding:pat = $($scan:tt)*) => {
+        let $binding = $crate::scan!($($scan)*)?;
+    };
+    ([$first:tt $(, $rest:tt)* $(,)?] <- $input:expr) => {{
+        let input = $input;
+        $crate::scan!($first <- input)
+            $(.or_else(|_| $crate::scan!($rest <- input)))*
+    }};
+    ($scan_string:tt <- $input:expr) => {{
+        let input = $input;
+        $crate::__from_pattern::<$crate::__scan_type!($crate; $scan_string)>($scan_string, input)
+            .map($crate::__scan_select!($crate; $scan_string))
+    }};
+    ($target:path { $($field:ident),* $(,)? }; $scan_string:tt <- $input:expr) => {{
+        let input = $input;
+        $crate::scan!($scan_string <- input)
+            .map($crate::__scan_fields!($crate; $scan_string; $target; $($field),*))
+    }};
+    ($target:ty; [$first:tt $(, $rest:tt)* $(,)?] <- $input:expr) => {{
+        let input = $input;
+        $crate::scan!($target; $first <- input)
+            $(.or_else(|_| $crate::scan!($target; $rest <- input)))*
+    }};
+    ($target:ty; $scan_string:tt <- $input:expr) => {{
+        let input = $input;
+        $crate::__from_pattern_exact::<$target>($crate::__scan_untyped!($crate; $scan_string), input)
+    }};
+    ($($t:tt)*) => {
+        compile_error!(
+            "invalid format.\nusage: scan!(\"scan literal\" <- value), scan!(Type; \"scan literal\" <- value), scan!(Type { fields }; \"scan literal\" <- value) or scan!(let pattern = \"scan literal\" <- value)"
+        );
+    };
+}
+
+/// Apply a `scan!` pattern to every line of the input.
+///
+/// Returns an iterator with one result per line. Blank lines are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate serde_scan;
+/// # fn main() {
+/// let input = "1 + 2\n3 + 4\n\n";
+///
+/// let sums: Vec<u32> = scan_all!("{u32} + {u32}" <- input)
+///     .map(|res| res.map(|(a, b)| a + b))
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(sums, [3, 7]);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! scan_all {
+    ($scan_string:tt <- $input:expr) => {
+        $input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| $crate::scan!($scan_string <- line))
+    };
+    ($target:path { $($field:ident),* $(,)? }; $scan_string:tt <- $input:expr) => {
+        $input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| $crate::scan!($target { $($field),* }; $scan_string <- line))
+    };
+    ($target:ty; $scan_string:tt <- $input:expr) => {
+        $input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| $crate::scan!($target; $scan_string <- line))
+    };
+    ($($t:tt)*) => {
+        compile_error!(
+            "invalid format.\nusage: scan_all!(\"scan literal\" <- value) or scan_all!(Type; \"scan literal\" <- value)"
+        );
+    };
+}
+
+/// Like `scan!`, but returns an `Option`, throwing out any error.
+///
+/// Handy in `filter_map`, when lines that don't match can be skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate serde_scan;
+/// # fn main() {
+/// let input = "move 1 2\njump\nmove 3 4";
+///
+/// let moves: Vec<(u32, u32)> = input
+///     .lines()
+///     .filter_map(|line| scan_opt!("move {} {}" <- line))
+///     .collect();
+///
+/// assert_eq!(moves, [(1, 2), (3, 4)]);
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! scan_opt {
+    ($($t:tt)*) => {
+        $crate::scan!($($t)*).ok()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use std::{string::String, string::ToString, vec::Vec};
+
+    #[test]
+    fn numbers() {
+        let a: u64 = from_str("64").unwrap();
+        let b: i64 = from_str("-64").unwrap();
+
+        assert_eq!(a, 64);
+        assert_eq!(b, -64);
+
+        let c: (u8, u64, i32) = from_str("+5 +0 +0x10").unwrap();
+        let d = from_str::<u32>("++5").unwrap_err();
+
+        let e: [u32; 3] = from_str("\u{feff}1 2 3").unwrap();
+
+        assert_eq!(c, (5, 0, 16));
+        assert_eq!(e, [1, 2, 3]);
+        assert_eq!(d.to_string(), "could not parse `++5` as u32 at token 1");
+        assert_eq!(peek_kind("+0"), Some(TokenKind::Unsigned));
+        assert_eq!(peek_kind("++5"), Some(TokenKind::String));
+    }
+
+    #[test]
+    fn big_numbers() {
+        use serde::de::{self, Deserializer, Visitor};
+        use std::fmt;
+
+        // 30!, which doesn't fit in a u64
+        let a: u128 = from_str("265252859812191058636308480000000").unwrap();
+        let b: i128 = from_str("-265252859812191058636308480000000").unwrap();
+
+        assert_eq!(a, 265252859812191058636308480000000);
+        assert_eq!(b, -265252859812191058636308480000000);
+
+        // records which visitor method `deserialize_any` picked
+        #[derive(Debug, PartialEq)]
+        enum Visited {
+            U64(u64),
+            U128(u128),
+            I128(i128),
+        }
+
+        struct AnyVisitor;
+
+        impl<'de> Visitor<'de> for AnyVisitor {
+            type Value = Visited;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an integer")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Visited, E> {
+                Ok(Visited::U64(v))
+            }
+
+            fn visit_u128<E: de::Error>(self, v: u128) -> Result<Visited, E> {
+                Ok(Visited::U128(v))
+            }
+
+            fn visit_i128<E: de::Error>(self, v: i128) -> Result<Visited, E> {
+                Ok(Visited::I128(v))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Visited {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                d.deserialize_any(AnyVisitor)
+            }
+        }
+
+        let c: Vec<Visited> = from_str("5 18446744073709551616 -9223372036854775809").unwrap();
+
+        assert_eq!(
+            c,
+            vec![
+                Visited::U64(5),
+                Visited::U128(18446744073709551616),
+                Visited::I128(-9223372036854775809),
+            ]
+        );
+    }
+
+    #[test]
+    fn size_hints() {
+        use serde::de::{self, Deserializer, SeqAccess, Visitor};
+        use std::fmt;
+
+        // records the size hint before each element
+        #[derive(Debug, PartialEq)]
+        struct Hints(Vec<Option<usize>>);
+
+        struct HintVisitor;
+
+        impl<'de> Visitor<'de> for HintVisitor {
+            type Value = Hints;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Hints, A::Error> {
+                let mut hints = vec![seq.size_hint()];
+                while seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    hints.push(seq.size_hint());
+                }
+                Ok(Hints(hints))
+            }
+        }
+
+        struct Triple(Hints);
+        struct Rest(Hints);
+
+        impl<'de> Deserialize<'de> for Triple {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                d.deserialize_tuple(3, HintVisitor).map(Triple)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Rest {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                d.deserialize_seq(HintVisitor).map(Rest)
+            }
+        }
+
+        let Triple(a) = from_str("1 2 3").unwrap();
+        let Rest(b) = from_str("1 2").unwrap();
+
+        assert_eq!(a, Hints(vec![Some(3), Some(2), Some(1), Some(0)]));
+        assert_eq!(b, Hints(vec![None, None, None]));
+    }
+
+    #[test]
+    fn floats() {
+        use serde::de::{self, Deserializer, Visitor};
+        use std::fmt;
+
+        // records which visitor method was called
+        #[derive(Debug, PartialEq)]
+        enum Visited {
+            F32(f32),
+            F64(f64),
+        }
+
+        struct F32Visitor;
+
+        impl<'de> Visitor<'de> for F32Visitor {
+            type Value = Visited;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a float")
+            }
+
+            fn visit_f32<E: de::Error>(self, v: f32) -> Result<Visited, E> {
+                Ok(Visited::F32(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Visited, E> {
+                Ok(Visited::F64(v))
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Visited {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                d.deserialize_f32(F32Visitor)
+            }
+        }
+
+        let a: Visited = from_str("0.1").unwrap();
+        let b: f32 = from_str("16777217").unwrap();
+
+        assert_eq!(a, Visited::F32(0.1));
+        assert_eq!(b, 16777216.0);
+
+        let c: Vec<f64> = from_str("inf -inf +Infinity NaN nan").unwrap();
+        assert_eq!(c[..3], [f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY]);
+        assert!(c[3].is_nan() && c[4].is_nan());
+
+        let opts = ScanOptions::new().reject_non_finite(true);
+        let d: (f32, f64) = opts.from_str("1.5 -2e3").unwrap();
+        let e = opts.from_str::<(f64, f32)>("1 NaN").unwrap_err();
+
+        assert_eq!(d, (1.5, -2000.0));
+        assert_eq!(e.to_string(), "expected a finite number at token 2");
+    }
+
+    #[test]
+    fn bools() {
+        let a: Vec<bool> = from_str("true TRUE t Y yes 1").unwrap();
+        let b: Vec<bool> = from_str("false False f N no 0").unwrap();
+        let c: Result<bool, _> = from_str("maybe");
+
+        assert!(a.iter().all(|&x| x));
+        assert!(b.iter().all(|&x| !x));
+        assert!(c.is_err());
+    }
+
+    #[test]
+    fn chars() {
+        let a: (char, char, char) = from_str("a é 😀").unwrap();
+        assert_eq!(a, ('a', 'é', '😀'));
+
+        let combining = from_str::<char>("e\u{301}").unwrap_err();
+        assert_eq!(
+            combining.to_string(),
+            "expected single character, got `e\u{301}` at token 1"
+        );
+        assert!(from_str::<char>("ab").is_err());
+    }
+
+    #[test]
+    fn char_escapes() {
+        let opts = ScanOptions::new().char_escapes(true);
+
+        let a: Vec<char> = opts.from_str("\\n \\t \\s \\0 \\\\ x").unwrap();
+        assert_eq!(a, ['\n', '\t', ' ', '\0', '\\', 'x']);
+
+        let b = opts.from_str::<char>("\\q").unwrap_err();
+        assert_eq!(b.to_string(), "unknown character escape `\\q` at token 1");
+
+        // off by default
+        assert!(from_str::<char>("\\n").is_err());
+    }
+
+    #[test]
+    fn grids() {
+        let input = "2 3\n1 2 3\n4 5 6\n";
+        let mut de = de::Deserializer::from_str(input);
+        let (rows, cols): (usize, usize) = de.deserialize().unwrap();
+
+        let grid: Vec<Vec<u8>> = from_grid(de.remainder(), rows, cols).unwrap();
+        assert_eq!(grid, [[1, 2, 3], [4, 5, 6]]);
+
+        let empty: Vec<Vec<u8>> = from_grid("", 0, 3).unwrap();
+        assert!(empty.is_empty());
+
+        let transposed: Vec<Vec<u8>> = from_grid_colmajor("1 4 2 5 3 6", 2, 3).unwrap();
+        assert_eq!(transposed, grid);
+
+        let column: Vec<Vec<char>> = from_grid_colmajor("a b c", 3, 1).unwrap();
+        assert_eq!(column, [['a'], ['b'], ['c']]);
+
+        let short = from_grid::<u32>("1 2 3", 2, 2).unwrap_err();
+        assert!(short.is_eof());
+    }
+
+    #[test]
+    fn borrowed_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row<'a> {
+            name: &'a str,
+            value: u32,
+        }
+
+        let input = String::from("alice 42 bob 7");
+        let rows: Vec<Row> = from_str(&input).unwrap();
+
+        assert_eq!(
+            rows[0],
+            Row {
+                name: "alice",
+                value: 42
+            }
+        );
+        assert_eq!(
+            rows[1],
+            Row {
+                name: "bob",
+                value: 7
+            }
+        );
+        // the names point into the input, rather than being copied
+        assert_eq!(rows[1].name.as_ptr(), input[9..].as_ptr());
+    }
+
+    #[test]
+    fn words() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Line<'a> {
+            id: u32,
+            #[serde(borrow)]
+            words: Vec<&'a str>,
+        }
+
+        let a: Vec<String> = from_str("the quick brown fox").unwrap();
+        let b: Vec<String> = from_str("jumps \"over the\" dog").unwrap();
+
+        assert_eq!(a, ["the", "quick", "brown", "fox"]);
+        assert_eq!(b, ["jumps", "over the", "dog"]);
+
+        let input = "7 lazy dogs";
+        let c: Line = from_str(input).unwrap();
+
+        assert_eq!(c.id, 7);
+        assert_eq!(c.words, ["lazy", "dogs"]);
+        // the words are borrowed straight from the input
+        assert_eq!(c.words[0].as_ptr(), input[2..].as_ptr());
+    }
+
+    #[test]
+    fn fill() {
+        let mut buf = [0u8; 3];
+
+        assert_eq!(fill_from_str("1 2 3 4", &mut buf).unwrap(), 3);
+        assert_eq!(buf, [1, 2, 3]);
+
+        assert_eq!(fill_from_str("9", &mut buf).unwrap(), 1);
+        assert_eq!(buf, [9, 2, 3]);
+
+        assert_eq!(fill_from_str("  ", &mut buf).unwrap(), 0);
+
+        let padded: [u32; 5] = from_str_padded("1 2 3").unwrap();
+        let full: [char; 2] = from_str_padded("a b c").unwrap();
+        let empty: [Option<u8>; 2] = from_str_padded("").unwrap();
+
+        assert_eq!(padded, [1, 2, 3, 0, 0]);
+        assert_eq!(full, ['a', 'b']);
+        assert_eq!(empty, [None, None]);
+        assert!(from_str_padded::<u8, 2>("1 x").is_err());
+        assert!(fill_from_str("5 x", &mut buf).unwrap_err().is_parse_error());
+    }
+
+    #[test]
+    fn units() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Unit;
+
+        let a: () = from_str("").unwrap();
+        let b: Unit = from_str("1").unwrap();
+        let c: (u32, (), Unit, u32) = from_str("1 2").unwrap();
+
+        assert_eq!(a, ());
+        assert_eq!(b, Unit);
+        assert_eq!(c, (1, (), Unit, 2));
+
+        // units don't read any tokens, so an unbounded sequence of them stops after one
+        let d: Vec<()> = from_str("1 2 3").unwrap();
+        let e: (u32, Vec<Unit>) = from_str("1 2").unwrap();
+
+        assert_eq!(d, [()]);
+        assert_eq!(e, (1, vec![Unit]));
+    }
+
+    #[test]
+    fn bitstrings() {
+        let a = from_bitstring("101101").unwrap();
+        let b = from_bitstring(" 1 0\r\n").unwrap();
+        let c = from_bitstring("10x1").unwrap_err();
+
+        assert_eq!(a, [true, false, true, true, false, true]);
+        assert_eq!(b, [true, false]);
+        assert!(from_bitstring("").unwrap().is_empty());
+        assert_eq!(
+            c,
+            ScanError::ParseFailed {
+                token: String::from("x"),
+                target: "bool",
+                at_token: 3,
+                near: None,
+            }
+        );
+    }
+
+    #[test]
+    fn short_tuples() {
+        let a = from_str::<(u32, u32, u32)>("1 2").unwrap_err();
+        let b = from_str::<[u32; 3]>("").unwrap_err();
+        let c = from_str_seq_prefixed::<Vec<u32>>("3 1 2").unwrap_err();
+
+        assert!(a.is_eof());
+        assert!(b.is_eof());
+        assert!(c.is_eof());
+    }
+
+    #[test]
+    fn tuples() {
+        let a: (f32,) = from_str("  45.34 ").unwrap();
+        let b: (u8, u8) = from_str("   3 4   ").unwrap();
+        let c: (u32, String, u32) = from_str(" 413 plus 612 ").unwrap();
+
+        assert_eq!(a.0, 45.34);
+        assert_eq!(b, (3, 4));
+        assert_eq!(c, (413, String::from("plus"), 612));
+    }
+
+    #[test]
+    fn strings() {
+        let a: (String, &str) = from_str("a a").unwrap();
+        let b: (String, &[u8]) = from_str("b b").unwrap();
+
+        assert_eq!(a.0.as_str(), a.1);
+        assert_eq!(b.0.as_bytes(), b.1);
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let input = String::from("foo bar");
+        let (a, b): (&str, &str) = from_str(&input).unwrap();
+
+        assert_eq!((a, b), ("foo", "bar"));
+        // the slices point into the original buffer
+        assert_eq!(a.as_ptr(), input.as_ptr());
+        assert_eq!(b.as_ptr(), input[4..].as_ptr());
+    }
+
+    #[test]
+    fn quoted_strings() {
+        let a: (String, String, u32) = from_str("name \"John  Doe\" 42").unwrap();
+        let b: (&str, String) = from_str("\"one\" \" two \"").unwrap();
+        let c = from_str::<(String, String)>("name \"John").unwrap_err();
+        let d = from_str::<(String, u32)>("\" 5").unwrap_err();
+
+        assert_eq!(a, (String::from("name"), String::from("John  Doe"), 42));
+        assert_eq!(b, ("one", String::from(" two ")));
+        // an unterminated quote is a parse error, not the input running out
+        assert!(c.is_parse_error());
+        assert_eq!(c.to_string(), "unterminated quoted string at token 2");
+        assert!(d.is_parse_error());
+
+        // the phrase is kept as written, separators and all
+        let comma = ScanOptions::new().whitespace(",");
+        let e: (&str, u32) = comma.from_str("\"a,b\",1").unwrap();
+        let f: (String, u32) = ScanOptions::new()
+            .also_skip(",")
+            .from_str("\"x, y\" 2")
+            .unwrap();
+
+        assert_eq!(e, ("a,b", 1));
+        assert_eq!(f, (String::from("x, y"), 2));
+
+        // tokens that aren't from a string are joined with spaces
+        let g: String = from_tokens(["\"a", "b\""].iter().cloned()).unwrap();
+        assert_eq!(g, "a b");
+    }
+
+    #[test]
+    fn bytes() {
+        let a: (u32, &str) = from_bytes(b"1 one").unwrap();
+        let b: Result<(u32, &str), _> = from_bytes(b"1 \xff");
+
+        assert_eq!(a, (1, "one"));
+        match b {
+            Err(ScanError::Utf8(_)) => {}
+            _ => panic!("expected utf8 error"),
+        }
+    }
+
+    #[test]
+    fn options() {
+        let a: Result<u32, ScanError> = from_str("    ");
+        let b: Option<u32> = from_str("   ").unwrap();
+        let c: Option<u32> = from_str(" 7 ").unwrap();
+
+        assert!(a.is_err());
+        assert_eq!(b, None);
+        assert_eq!(c, Some(7));
+    }
+
+    #[test]
+    fn option_fields() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Cell {
+            a: Option<u32>,
+            b: Option<u32>,
+            c: u32,
+        }
+
+        let a: Cell = from_str("- 2 3").unwrap();
+        let b: Vec<Option<i32>> = from_str("1 - -3").unwrap();
+        let c: Cell = ScanOptions::new()
+            .none_token("null")
+            .from_str("1 null 3")
+            .unwrap();
+
+        assert_eq!(
+            a,
+            Cell {
+                a: None,
+                b: Some(2),
+                c: 3,
+            }
+        );
+        assert_eq!(b, vec![Some(1), None, Some(-3)]);
+        assert_eq!(c.b, None);
+    }
+
+    #[test]
+    fn default_fields() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct P {
+            a: u32,
+            #[serde(default)]
+            b: u32,
+            c: Option<u32>,
+        }
+
+        let a: P = from_str("5").unwrap();
+        let b: P = from_str("5 6 7").unwrap();
+        let c = from_str::<P>("").unwrap_err();
+
+        assert_eq!(
+            a,
+            P {
+                a: 5,
+                b: 0,
+                c: None
+            }
+        );
+        assert_eq!(
+            b,
+            P {
+                a: 5,
+                b: 6,
+                c: Some(7),
+            }
+        );
+        assert!(c.is_eof());
+
+        // the last field is still read, so a trailing container can be empty
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Q {
+            a: u32,
+            #[serde(default)]
+            b: u32,
+            rest: Vec<u32>,
+        }
+
+        let d: Q = from_str("5").unwrap();
+        assert_eq!(
+            d,
+            Q {
+                a: 5,
+                b: 0,
+                rest: vec![]
+            }
+        );
+    }
+
+    #[test]
+    fn three_ways() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Triple {
+            a: u32,
+            b: u32,
+            c: u32,
+        }
+
+        let s = r#" 1 
+                2 
+        3 "#;
+
+        let a: [u32; 3] = from_str(s).unwrap();
+        assert_eq!(a, [1, 2, 3]);
+
+        let b: (u32, u32, u32) = from_str(s).unwrap();
+        assert_eq!(b, (1, 2, 3));
+
+        let c: Triple = from_str(s).unwrap();
+        assert_eq!(c, Triple { a: 1, b: 2, c: 3 });
+    }
+
+    #[test]
+    fn enums() {
+        let color_list = r#"
+            red
+            blue
+            green
+            green
+            red
+            blue
+        "#;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Color {
+            Red,
+            Blue,
+            Green,
+        }
+
+        let colors: Vec<Color> = from_str(color_list).unwrap();
+
+        assert_eq!(colors.len(), 6);
+        assert_eq!(colors[3], Color::Green);
+    }
+
+    #[test]
+    fn enum_tuple_streams() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Instr {
+            Jump(u32, u32),
+            Path(Vec<u32>),
+            Halt,
+        }
+
+        let a: Vec<Instr> = from_str("jump 1 2 jump 3 4 halt").unwrap();
+        let b: (Instr, u32) = from_str("jump 5 6 7").unwrap();
+        // a single field is a newtype variant, so it can still take the rest of the input
+        let c: Instr = from_str("path 1 2 3").unwrap();
+
+        assert_eq!(a, vec![Instr::Jump(1, 2), Instr::Jump(3, 4), Instr::Halt]);
+        assert_eq!(b, (Instr::Jump(5, 6), 7));
+        assert_eq!(c, Instr::Path(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn strict_unit_variants() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Command {
+            Help,
+            Size(u32),
+        }
+
+        let a: Command = from_str("help extra").unwrap();
+        assert_eq!(a, Command::Help);
+
+        let opts = ScanOptions::new().strict_unit_variants(true);
+        let b: Command = opts.from_str("help\n").unwrap();
+        let c: Command = opts.from_str("size 3").unwrap();
+        let d = opts.from_str::<Command>("help extra").unwrap_err();
+        let e: (Command, u32) = opts.from_str("help 1").unwrap();
+        let f: Vec<Command> = opts.from_str("help size 2 help").unwrap();
+
+        assert_eq!(b, Command::Help);
+        assert_eq!(c, Command::Size(3));
+        assert_eq!(e, (Command::Help, 1));
+        assert_eq!(f, [Command::Help, Command::Size(2), Command::Help]);
+        assert_eq!(
+            d,
+            ScanError::TrailingTokens {
+                token: String::from("extra"),
+                at_token: 2,
+                near: None,
+            }
+        );
+    }
+
+    #[test]
+    fn enum_case() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum Element {
+            Fire,
+            Water(u32),
+        }
+
+        let opts = ScanOptions::new().case_insensitive_variants(true);
+
+        let a: Vec<Element> = opts.from_str("FIRE fire Fire WATER 3").unwrap();
+        let b = from_str::<Element>("FIRE").unwrap_err();
+        let c = opts.from_str::<Element>("earth").unwrap_err();
+
+        assert_eq!(
+            a,
+            vec![
+                Element::Fire,
+                Element::Fire,
+                Element::Fire,
+                Element::Water(3)
+            ]
+        );
+        assert!(b.to_string().contains("unknown variant `FIRE`"));
+        assert!(c.to_string().contains("unknown variant `earth`"));
+    }
+
+    #[test]
+    fn enum_tuple() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        enum EnumTuple {
+            Variant(i32),
+            Tuple(String, String, usize),
+        }
+
+        // this might work in the future
+        let a: EnumTuple = from_str("variant 1").unwrap();
+        let b: EnumTuple = from_str("tuple two three 4").unwrap();
+
+        assert_eq!(a, EnumTuple::Variant(1));
+        assert_eq!(
+            b,
+            EnumTuple::Tuple("two".to_string(), "three".to_string(), 4)
+        );
+    }
+
+    #[test]
+    fn trailing_vec() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Header {
+            header: u32,
+            rest: Vec<u32>,
+        }
+
+        let a: Header = from_str("5 1 2 3 4").unwrap();
+        assert_eq!(
+            a,
+            Header {
+                header: 5,
+                rest: vec![1, 2, 3, 4],
+            }
+        );
+
+        let b: Header = from_str("5").unwrap();
+        assert!(b.rest.is_empty());
+    }
+
+    #[test]
+    fn ignored() {
+        use serde::de::IgnoredAny;
+
+        #[derive(Deserialize, Debug)]
+        struct Row {
+            id: u32,
+            _name: IgnoredAny,
+            score: u32,
+        }
+
+        let row: Row = from_str("1 bob 90").unwrap();
+        assert_eq!((row.id, row.score), (1, 90));
+
+        let (a, _, b): (u32, IgnoredAny, u32) = from_str("1 2.5 3").unwrap();
+        assert_eq!((a, b), (1, 3));
+    }
+
+    #[test]
+    fn radix_prefixes() {
+        let a: (u32, u32) = from_str("0xff 0b1010").unwrap();
+        let b: (i8, i64, u16) = from_str("-0x80 0o17 +0XA").unwrap();
+        let c: Result<u8, _> = from_str("0x100");
+        let d: String = from_str("0x10").unwrap();
+
+        assert_eq!(a, (255, 10));
+        assert_eq!(b, (-128, 15, 10));
+        assert!(c.is_err());
+        assert_eq!(d, "0x10");
+
+        // the sign goes before the prefix
+        assert!(from_str::<i32>("0x-5").unwrap_err().is_parse_error());
+        assert!(from_str::<i32>("0x+5").unwrap_err().is_parse_error());
+        assert!(from_str::<u32>("-0b+1").unwrap_err().is_parse_error());
+    }
+
+    #[test]
+    fn scaled() {
+        let a: u32 = from_str_scaled(2, "12.34").unwrap();
+        let b: (i64, i64, i64) = from_str_scaled(3, "-1.5 .25 7.").unwrap();
+        let c: f32 = from_str_scaled(2, "1.125").unwrap();
+
+        assert_eq!(a, 1234);
+        assert_eq!(b, (-1500, 250, 7000));
+        assert_eq!(c, 1.125);
+
+        assert!(from_str_scaled::<u32>(2, "12.345").is_err());
+        assert!(from_str_scaled::<u32>(2, "1.-5").is_err());
+        assert!(from_str_scaled::<u32>(2, "1.2.3")
+            .unwrap_err()
+            .is_parse_error());
+        for &s in &[".", "-", "-.", "+", "--1", "1-.5", "a.5"] {
+            assert!(
+                from_str_scaled::<i32>(2, s).unwrap_err().is_parse_error(),
+                "{}",
+                s
+            );
+        }
+        assert!(from_str_scaled::<u8>(2, "2.56")
+            .unwrap_err()
+            .is_parse_error());
+    }
+
+    #[test]
+    fn radixes() {
+        let a: Vec<u8> = from_str_with_radix(16, "ff a0 3c").unwrap();
+        let b: (i8, u32) = from_str_with_radix(2, "-101 +11").unwrap();
+        let c = from_str_with_radix::<u8>(16, "0xff").unwrap_err();
+
+        assert_eq!(a, [0xff, 0xa0, 0x3c]);
+        assert_eq!(b, (-5, 3));
+        assert!(c.is_parse_error());
+    }
+
+    #[test]
+    fn maps() {
+        use std::collections::{BTreeMap, HashMap};
+
+        let a: HashMap<String, u32> = from_str("a 1 b 2 c 3").unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a["b"], 2);
+
+        let (b, n): (BTreeMap<char, u32>, u32) = from_str_counted_map("2 a 1 b 2 7").unwrap();
+        assert_eq!(b.into_iter().collect::<Vec<_>>(), vec![('a', 1), ('b', 2)]);
+        assert_eq!(n, 7);
+
+        let c: Result<HashMap<char, u32>, _> = from_str_counted_map("3 a 1 b 2");
+        assert!(c.is_err());
+
+        let sorted: BTreeMap<String, u32> = from_str("b 2 c 3 a 1").unwrap();
+        assert_eq!(sorted.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+
+        // a key without a value
+        let half = from_str::<HashMap<char, u32>>("a 1 b").unwrap_err();
+        assert!(half.is_eof());
+
+        let bad = from_str::<HashMap<char, u32>>("a 1 b x c 3").unwrap_err();
+        assert_eq!(bad.to_string(), "could not parse `x` as u32 at token 4");
+
+        let opts = ScanOptions::new().kv_separator('=');
+        let d: HashMap<String, u32> = opts.from_str("a=1 b=2 c=0x10").unwrap();
+        assert_eq!(d.len(), 3);
+        assert_eq!(d["c"], 16);
+
+        let e = opts.from_str::<HashMap<String, u32>>("a=1 b2").unwrap_err();
+        assert_eq!(e.to_string(), "expected `=` in map entry `b2` at token 2");
+
+        let f = opts
+            .from_str::<HashMap<String, u32>>("a=1 b=x")
+            .unwrap_err();
+        assert_eq!(f.to_string(), "could not parse `x` as u32 at token 2");
+
+        // an empty value is malformed, not the end of the input
+        let g = opts
+            .from_str::<HashMap<String, String>>("a=x b=")
+            .unwrap_err();
+        assert!(g.is_parse_error());
+        assert_eq!(g.to_string(), "missing value for `b` at token 2");
+
+        let h: HashMap<String, Option<u32>> = opts.from_str("a= b=2").unwrap();
+        assert_eq!(h["a"], None);
+        assert_eq!(h["b"], Some(2));
+    }
+
+    #[test]
+    fn kv_structs() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct P {
+            a: u32,
+            b: u32,
+        }
+
+        let opts = ScanOptions::new().kv_separator('=');
+
+        let p: P = opts.from_str("b=2 a=1").unwrap();
+        let ps: Vec<P> = opts.from_str("a=1 b=2 b=4 a=3").unwrap();
+
+        assert_eq!(p, P { a: 1, b: 2 });
+        assert_eq!(ps, [P { a: 1, b: 2 }, P { a: 3, b: 4 }]);
+        assert!(opts.from_str::<P>("b=2").unwrap_err().is_eof());
+        assert_eq!(
+            opts.from_str::<P>("a= b=2").unwrap_err().to_string(),
+            "missing value for `a` at token 1"
+        );
+
+        // unknown keys are skipped, without taking the place of a field
+        let q: P = opts.from_str("z=9 a=1 b=2").unwrap();
+        let qs: Vec<P> = opts.from_str("a=1 z=9 b=2 b=4 a=3").unwrap();
+
+        assert_eq!(q, P { a: 1, b: 2 });
+        assert_eq!(qs, [P { a: 1, b: 2 }, P { a: 3, b: 4 }]);
+    }
+
+    #[test]
+    fn counted_seqs() {
+        use std::collections::BTreeSet;
+
+        let a: Vec<(u32, char)> = from_str_seq_prefixed("2 1 a 2 b 3 c").unwrap();
+        let b: BTreeSet<u32> = from_str_seq_prefixed("0 1 2").unwrap();
+        let c = from_str_seq_prefixed::<Vec<u32>>("two 1 2").unwrap_err();
+
+        assert_eq!(a, vec![(1, 'a'), (2, 'b')]);
+        assert!(b.is_empty());
+        assert!(c.is_parse_error());
+
+        let opts = ScanOptions::new().counted_seqs(true);
+        let d: (Vec<u32>, String) = opts.from_str("3 1 2 3 done").unwrap();
+        let e: Vec<Vec<char>> = opts.from_str("2 1 a 2 b c").unwrap();
+
+        assert_eq!(d, (vec![1, 2, 3], String::from("done")));
+        assert_eq!(e, [vec!['a'], vec!['b', 'c']]);
+        assert!(opts.from_str::<Vec<u32>>("3 1 2").unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn byte_bufs() {
+        let bytes: Vec<u8> = from_str("0 1 2 255").unwrap();
+        assert_eq!(bytes[0], 0x00);
+        assert_eq!(bytes.len(), 4);
+
+        let byte_str: &[u8] = from_str("0x32323").unwrap();
+        assert_eq!(byte_str, b"0x32323");
+
+        // a byte string only takes a single token
+        let (word, n): (&[u8], u32) = from_str("hello 5").unwrap();
+        assert_eq!(word, b"hello");
+        assert_eq!(n, 5);
+    }
+
+    #[test]
+    fn internally_tagged() {
+        // spotted by how serde_derive describes the enum, so this also checks that wording
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(tag = "type", rename_all = "lowercase")]
+        enum Command {
+            Move { x: i32, y: i32 },
+            Say { word: String, times: u32 },
+            Stop,
+        }
+
+        let a: Command = from_str("move 3 -4").unwrap();
+        let b: Command = from_str("say hello 2").unwrap();
+        let c: Command = from_str("stop").unwrap();
+        let d: (u32, Command) = from_str("1 move 0 0").unwrap();
+
+        assert_eq!(a, Command::Move { x: 3, y: -4 });
+        assert_eq!(
+            b,
+            Command::Say {
+                word: String::from("hello"),
+                times: 2
+            }
+        );
+        assert_eq!(c, Command::Stop);
+        assert_eq!(d, (1, Command::Move { x: 0, y: 0 }));
+
+        assert!(from_str::<Command>("jump 1").is_err());
+        assert!(from_str::<Command>("").is_err());
+    }
+
+    #[test]
+    fn enum_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
         #[serde(rename_all = "snake_case")]
-        enum Bad {
-            StructVariant { a: f64, b: f64 },
+        enum Command {
+            Move { x: i32, y: i32 },
+            Stop,
+        }
+
+        let a: Command = from_str("move 3 -4").unwrap();
+        let b: Vec<Command> = from_str("stop move 1 2 stop").unwrap();
+
+        assert_eq!(a, Command::Move { x: 3, y: -4 });
+        assert_eq!(
+            b,
+            vec![Command::Stop, Command::Move { x: 1, y: 2 }, Command::Stop]
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct VecWithStuff {
+            vec: Vec<u32>,
+            stuff: String,
+        }
+
+        // this will work in the future
+        let d: Result<VecWithStuff, _> = from_str("1 2 3 4 6 Stuff");
+        assert!(d.is_err());
+
+        // with the vec last, it takes every remaining token
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct StuffWithVec {
+            n: usize,
+            stuff: String,
+            vec: Vec<i32>,
+        }
+
+        let e: StuffWithVec = from_str("3 Stuff 10 -20 30").unwrap();
+        assert_eq!(
+            e,
+            StuffWithVec {
+                n: 3,
+                stuff: String::from("Stuff"),
+                vec: vec![10, -20, 30],
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn readers() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Triple {
+            a: u32,
+            b: u32,
+            c: u32,
+        }
+
+        let input = "1 2\n3\n4 5 6\n";
+
+        let a: Triple = from_reader(input.as_bytes()).unwrap();
+        assert_eq!(a, Triple { a: 1, b: 2, c: 3 });
+
+        let b: Result<Triple, _> = from_reader("1 2\n".as_bytes());
+        assert!(b.is_err());
+
+        let t: (u32, [u32; 2]) = from_reader(input.as_bytes()).unwrap();
+        assert_eq!(t, (1, [2, 3]));
+
+        let mut c = read::buf_read_lines::<_, (u32, u32)>("1 2\n3 4\nx 5".as_bytes());
+        assert_eq!(c.next().unwrap().unwrap(), (1, 2));
+        assert_eq!(c.next().unwrap().unwrap(), (3, 4));
+        assert!(c.next().unwrap().unwrap_err().is_parse_error());
+        assert!(c.next().is_none());
+
+        let d: Vec<(u32, u32)> = read::buf_read_n_lines("1 2\n3 4\n5 6".as_bytes(), 2).unwrap();
+        assert_eq!(d, [(1, 2), (3, 4)]);
+
+        let e = read::buf_read_n_lines::<_, (u32, u32)>("1 2\n".as_bytes(), 2).unwrap_err();
+        assert!(e.is_eof());
+        let huge = read::buf_read_n_lines::<_, u32>("1\n".as_bytes(), usize::MAX).unwrap_err();
+        assert!(huge.is_eof());
+
+        // blank lines are skipped, instead of reading as the input running out
+        let mut f = read::buf_read_lines::<_, u32>("1\n\n  \n2\n\n".as_bytes());
+        assert_eq!(f.next().unwrap().unwrap(), 1);
+        assert_eq!(f.next().unwrap().unwrap(), 2);
+        assert!(f.next().is_none());
+
+        let g: Vec<u32> = read::buf_read_n_lines("\n1\n\n2\n".as_bytes(), 2).unwrap();
+        assert_eq!(g, [1, 2]);
+    }
+
+    #[test]
+    fn iters() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let points: Vec<Point> = from_str_iter("1 2\n3 4\n-5 6\n")
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[2], Point { x: -5, y: 6 });
+
+        let mut bad = from_str_iter::<Point>("1 2 3 x 5 6");
+        assert!(bad.next().unwrap().is_ok());
+        assert!(bad.next().unwrap().is_err());
+        assert!(bad.next().is_none());
+    }
+
+    #[test]
+    fn scan_options() {
+        let a: (u32, u32) = ScanOptions::new().from_str("1\n2").unwrap();
+        let b: Vec<u32> = ScanOptions::new()
+            .also_skip(",;")
+            .from_str("1,2;3 4")
+            .unwrap();
+        let c: (u32, String) = ScanOptions::new()
+            .whitespace(",")
+            .from_str("1,two words")
+            .unwrap();
+        let d: (u32, u32) = from_str_skipping("-", "1-2").unwrap();
+        let e: (u32, u32) = from_str_skipping_owned(vec!['/'], "1/2").unwrap();
+
+        assert_eq!(a, (1, 2));
+        assert_eq!(b, vec![1, 2, 3, 4]);
+        assert_eq!(c, (1, String::from("two words")));
+        assert_eq!(d, (1, 2));
+        assert_eq!(e, (1, 2));
+
+        let opts = ScanOptions::new().zero_width_spaces(true);
+        let g: [u32; 3] = opts
+            .from_str("\u{feff}1\u{200b}2 \u{200b}3\u{200b}")
+            .unwrap();
+        assert_eq!(g, [1, 2, 3]);
+
+        let opts = ScanOptions::new().digit_separators("_");
+        let j: (u32, f64, String) = opts.from_str("1_000 2_500.5 a_b").unwrap();
+        assert_eq!(j, (1000, 2500.5, String::from("a_b")));
+
+        let opts = ScanOptions::new().digit_separators(",");
+        let k: (i64, Option<u8>) = opts.from_str("-1,000,000 1,2").unwrap();
+        assert_eq!(k, (-1_000_000, Some(12)));
+        assert!(from_str::<u32>("1,000").is_err());
+
+        let opts = ScanOptions::new().char_as_code(true);
+        let l: (u32, u8, char, i64) = opts.from_str("A 7 B é").unwrap();
+        assert_eq!(l, (65, 7, 'B', 233));
+        assert!(opts.from_str::<i8>("é").unwrap_err().is_parse_error());
+        assert!(from_str::<u32>("A").is_err());
+
+        let opts = ScanOptions::new().single_line(true);
+        let i: (u32, u32) = opts.from_str("\n1 2\n3").unwrap();
+        assert_eq!(i, (1, 2));
+        assert!(opts.from_str::<(u32, u32)>("1\n2").unwrap_err().is_eof());
+
+        let opts = ScanOptions::new().comment_prefix('#');
+        let f: (u32, Vec<u32>) = opts.from_str("3 # count\n1 2 3 #\n# done").unwrap();
+        assert_eq!(f, (3, vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn raw_bytes() {
+        use serde_bytes::ByteBuf;
+
+        let opts = ScanOptions::new().raw_bytes(true);
+        let (word, n): (ByteBuf, u32) = opts.from_str("hello 5").unwrap();
+        assert_eq!(word.as_ref(), b"hello");
+        assert_eq!(n, 5);
+
+        let v: Vec<u8> = opts.from_str("1 2 3").unwrap();
+        assert_eq!(v, [1, 2, 3]);
+    }
+
+    #[test]
+    fn records() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row<'a> {
+            name: &'a str,
+            scores: (u32, u32),
+        }
+
+        let mut records = RecordDeserializer::new("amy 3 4\r\nben 5\n6\n");
+
+        let amy: Row = records.next().unwrap();
+        assert_eq!(
+            amy,
+            Row {
+                name: "amy",
+                scores: (3, 4)
+            }
+        );
+
+        let ben = records.next::<Row>().unwrap_err();
+        assert!(ben.is_eof());
+        assert_eq!(records.line(), 2);
+
+        assert_eq!(records.next::<u32>().unwrap(), 6);
+        assert!(records.next::<u32>().unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn scan_str() {
+        let line = String::from("move 3 left");
+
+        let (verb, n, dir): (&str, u32, String) = line.scan().unwrap();
+        assert_eq!((verb, n, dir.as_str()), ("move", 3, "left"));
+
+        assert!("x".scan::<u32>().unwrap_err().is_parse_error());
+    }
+
+    #[test]
+    fn scanner() {
+        let mut scanner = Scanner::new("3 1 2 3 done");
+
+        let n: usize = scanner.next().unwrap();
+        let items: Vec<u32> = (0..n).map(|_| scanner.next().unwrap()).collect();
+
+        assert_eq!(items, vec![1, 2, 3]);
+        assert!(scanner.remaining());
+        assert_eq!(scanner.next::<&str>().unwrap(), "done");
+        assert!(!scanner.remaining());
+
+        // checking doesn't need a mutable scanner, or move it along
+        let scanner = Scanner::new(" 7 ");
+        assert!(scanner.remaining());
+        assert!(scanner.remaining());
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut scanner = Scanner::new("1 two 3");
+        assert_eq!(scanner.next::<u32>().unwrap(), 1);
+
+        let saved = scanner.checkpoint();
+        let err = scanner.next::<u32>().unwrap_err();
+        assert!(err.is_parse_error());
+
+        scanner.restore(&saved);
+        assert_eq!(scanner.next::<String>().unwrap(), "two");
+        assert_eq!(scanner.next::<u32>().unwrap(), 3);
+        assert!(!scanner.remaining());
+
+        // a checkpoint can be restored more than once, and keeps the token count
+        scanner.restore(&saved);
+        scanner.restore(&saved);
+        let err = scanner.next::<(u32, u32)>().unwrap_err();
+        assert_eq!(
+            err,
+            ScanError::ParseFailed {
+                token: String::from("two"),
+                target: "u32",
+                at_token: 2,
+                near: None,
+            }
+        );
+    }
+
+    #[test]
+    fn token_kinds() {
+        assert_eq!(peek_kind("  7 x"), Some(TokenKind::Unsigned));
+        assert_eq!(peek_kind("-7"), Some(TokenKind::Integer));
+        assert_eq!(
+            peek_kind("18446744073709551616"),
+            Some(TokenKind::Unsigned128)
+        );
+        assert_eq!(peek_kind("1.5"), Some(TokenKind::Float));
+        assert_eq!(peek_kind("x 7"), Some(TokenKind::Char));
+        assert_eq!(peek_kind("move 7"), Some(TokenKind::String));
+        assert_eq!(peek_kind(" \n"), None);
+
+        let table = [
+            ("007", TokenKind::Unsigned),
+            ("+5", TokenKind::Unsigned),
+            ("-007", TokenKind::Integer),
+            ("5.", TokenKind::Float),
+            ("5e2", TokenKind::Float),
+            ("-0.0", TokenKind::Float),
+            ("inf", TokenKind::Float),
+            ("nan", TokenKind::Float),
+            ("0x10", TokenKind::Unsigned),
+            ("-0b11", TokenKind::Integer),
+            ("0xg", TokenKind::String),
+            ("5_000", TokenKind::String),
+            ("2024-01-15", TokenKind::String),
+            ("12:30", TokenKind::String),
+            ("1e", TokenKind::String),
+            ("+", TokenKind::Char),
+        ];
+
+        for &(token, kind) in &table {
+            assert_eq!(peek_kind(token), Some(kind), "classifying `{}`", token);
+        }
+    }
+
+    #[test]
+    fn date_like() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Event {
+            date: String,
+            name: String,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Field {
+            Number(i64),
+            Text(String),
+        }
+
+        let a: Event = from_str("2024-01-15 event").unwrap();
+        assert_eq!(a.date, "2024-01-15");
+        assert_eq!(a.name, "event");
+
+        // `deserialize_any` only picks a number when the whole token is one
+        let b: Vec<Field> = from_str("2024-01-15 -3").unwrap();
+        assert_eq!(
+            b,
+            [Field::Text(String::from("2024-01-15")), Field::Number(-3)]
+        );
+    }
+
+    #[test]
+    fn decimals() {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Price {
+            item: String,
+            #[serde(with = "rust_decimal::serde::str")]
+            exact: Decimal,
+            approx: Decimal,
+        }
+
+        let digits = "0.1234567890123456789012345";
+        let a: Price = from_str(&format!("tea {} 12.5", digits)).unwrap();
+
+        assert_eq!(a.item, "tea");
+        assert_eq!(a.exact, Decimal::from_str(digits).unwrap());
+        assert_eq!(a.approx, Decimal::from_str("12.5").unwrap());
+
+        // `deserialize_str` gets the token as written, even when it looks like a float
+        let b: &str = from_str(digits).unwrap();
+        assert_eq!(b, digits);
+
+        // a decimal is read through `deserialize_any`, and is still a single token
+        let c = from_str_exact::<Decimal>("1.5 x").unwrap_err();
+        assert!(matches!(c, ScanError::TrailingTokens { at_token: 2, .. }));
+    }
+
+    #[test]
+    fn deserialize_with() {
+        use serde::de::{Deserializer, Error};
+
+        // reads durations like `3h` or `90`, in seconds
+        fn seconds<'de, D: Deserializer<'de>>(d: D) -> Result<u64, D::Error> {
+            let s = <&str>::deserialize(d)?;
+
+            let (n, unit) = match s.char_indices().last() {
+                Some((i, 'h')) => (&s[..i], 3600),
+                Some((i, 'm')) => (&s[..i], 60),
+                _ => (s, 1),
+            };
+
+            n.parse::<u64>()
+                .map(|n| n * unit)
+                .map_err(|_| D::Error::custom(format!("bad duration `{}`", s)))
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Timer {
+            name: String,
+            #[serde(deserialize_with = "seconds")]
+            length: u64,
+            #[serde(deserialize_with = "seconds")]
+            repeat: u64,
+        }
+
+        // `90` looks like a number, but still reaches `seconds` as a string
+        let a: Timer = from_str("tea 3h 90").unwrap();
+        assert_eq!(
+            a,
+            Timer {
+                name: String::from("tea"),
+                length: 10800,
+                repeat: 90,
+            }
+        );
+
+        let b = from_str::<Timer>("tea 3x 1").unwrap_err();
+        assert_eq!(
+            b,
+            ScanError::De {
+                msg: String::from("bad duration `3x`"),
+                at_token: 2,
+                near: None,
+            }
+        );
+    }
+
+    #[test]
+    fn newtypes() {
+        use std::time::Duration;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Seconds(u64);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Ratio(f64);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(transparent)]
+        struct Name(String);
+
+        // untagged enums pick a variant with `deserialize_any`
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Field {
+            Time(Seconds),
+            Ratio(Ratio),
+            Name(Name),
+        }
+
+        let a: (Seconds, Ratio, Name) = from_str("30 0.5 bob").unwrap();
+        let b: Vec<Field> = from_str("30 0.5 bob").unwrap();
+        let c: Duration = from_str("5 500").unwrap();
+
+        assert_eq!(a, (Seconds(30), Ratio(0.5), Name(String::from("bob"))));
+        assert_eq!(
+            b,
+            vec![
+                Field::Time(Seconds(30)),
+                Field::Ratio(Ratio(0.5)),
+                Field::Name(Name(String::from("bob"))),
+            ]
+        );
+        assert_eq!(c, Duration::new(5, 500));
+    }
+
+    #[test]
+    fn renamed_fields() {
+        // renaming doesn't reorder anything
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Swapped {
+            #[serde(rename = "second")]
+            first: u32,
+            #[serde(rename = "first")]
+            second: u32,
+            third: u32,
         }
 
-        // this might work in the future
-        let c: Result<Bad, _> = from_str("struct_variant 0.4 0.5");
+        let a: Swapped = from_str("1 2 3").unwrap();
 
-        assert!(c.is_err());
+        assert_eq!(
+            a,
+            Swapped {
+                first: 1,
+                second: 2,
+                third: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn addresses() {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+        let (a, b, c): (IpAddr, IpAddr, SocketAddr) =
+            from_str("127.0.0.1 ::1 10.0.0.1:8080").unwrap();
+
+        assert_eq!(a, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(b, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert_eq!(c, "10.0.0.1:8080".parse().unwrap());
+
+        for &token in &["127.0.0.1", "::1", "10.0.0.1:8080"] {
+            assert_eq!(peek_kind(token), Some(TokenKind::String));
+        }
+    }
 
+    #[test]
+    fn nested_structs() {
         #[derive(Deserialize, Debug, PartialEq)]
-        struct VecWithStuff {
-            vec: Vec<u32>,
-            stuff: String,
+        struct Pos {
+            x: i32,
+            y: i32,
         }
 
-        // this will work in the future
-        let d: Result<VecWithStuff, _> = from_str("1 2 3 4 6 Stuff");
-        assert!(d.is_err())
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Stats {
+            hp: u32,
+            name: String,
+        }
+
+        // nested structs take their fields from the same line, like `flatten` would
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Unit {
+            id: u32,
+            pos: Pos,
+            stats: Stats,
+        }
+
+        let a: Unit = from_str("7 -1 2 100 knight").unwrap();
+
+        assert_eq!(
+            a,
+            Unit {
+                id: 7,
+                pos: Pos { x: -1, y: 2 },
+                stats: Stats {
+                    hp: 100,
+                    name: String::from("knight"),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Pos {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Unit {
+            id: u32,
+            #[serde(flatten)]
+            pos: Pos,
+        }
+
+        // serde reads the struct as a map, without its field names, so they
+        // have to be in the input
+        let a: Unit = from_str("x -1 id 7 y 2").unwrap();
+        assert_eq!(
+            a,
+            Unit {
+                id: 7,
+                pos: Pos { x: -1, y: 2 },
+            }
+        );
+        assert!(from_str::<Unit>("7 -1 2").is_err());
+
+        // named fields can be matched up
+        let opts = ScanOptions::new().kv_separator('=');
+        let b: Unit = opts.from_str("y=2 id=7 x=-1").unwrap();
+        assert_eq!(
+            b,
+            Unit {
+                id: 7,
+                pos: Pos { x: -1, y: 2 },
+            }
+        );
+    }
+
+    #[test]
+    fn nested_arrays() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Grid {
+            size: u32,
+            row: [u32; 3],
+            corners: [(u8, u8); 2],
+            last: u32,
+        }
+
+        let a: Grid = from_str("1 4 5 6 0 0 9 9 7 8").unwrap();
+
+        assert_eq!(
+            a,
+            Grid {
+                size: 1,
+                row: [4, 5, 6],
+                corners: [(0, 0), (9, 9)],
+                last: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn tokens() {
+        let a: (u32, u32, u32) = from_tokens(vec!["1", "2", "3"]).unwrap();
+        let b: (String, u32) = from_tokens(vec!["two words", "5"]).unwrap();
+        let c = from_tokens::<_, u32>(vec![]).unwrap_err();
+
+        assert_eq!(a, (1, 2, 3));
+        assert_eq!(b, (String::from("two words"), 5));
+        assert!(c.is_eof());
+    }
+
+    #[test]
+    fn split_tokens() {
+        let a: Vec<&str> = super::tokens("\u{feff}1  two\r\n\"3 4\"").collect();
+        assert_eq!(a, ["1", "two", "\"3", "4\""]);
+        assert_eq!(super::tokens(" \n\t").next(), None);
+    }
+
+    #[test]
+    fn consuming() {
+        let (a, rest): ((u32, char), _) = from_str_consuming("  1 x\n2 y").unwrap();
+        let (b, rest): (Option<u32>, _) = from_str_consuming(rest).unwrap();
+        let (c, rest): (char, _) = from_str_consuming(rest).unwrap();
+        let (d, rest): (Vec<u32>, _) = from_str_consuming(rest).unwrap();
+
+        assert_eq!((a, b, c), ((1, 'x'), Some(2), 'y'));
+        assert!(d.is_empty());
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn exact() {
+        let a: (u32, u32) = from_str_exact(" 1 2\n").unwrap();
+        let b = from_str_exact::<(u32, u32)>("1 2 3").unwrap_err();
+
+        assert_eq!(a, (1, 2));
+        assert_eq!(b.to_string(), "unexpected trailing input `3` at token 3");
+        assert!(b.is_parse_error());
+    }
+
+    #[test]
+    fn error_messages() {
+        #[derive(Deserialize, Debug)]
+        #[serde(rename_all = "snake_case")]
+        enum Color {
+            Red,
+        }
+
+        let err = from_str::<Color>("purple").unwrap_err();
+        assert!(err.to_string().contains("unknown variant `purple`"));
+
+        let err = from_str::<(u32, u32)>("3 12x").unwrap_err();
+        assert_eq!(err.to_string(), "could not parse `12x` as u32 at token 2");
+
+        let err = from_str::<(u8, i8)>("255 -129").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value `-129` out of range for i8 at token 2"
+        );
+
+        match from_str::<(u32, Color)>("3 purple").unwrap_err() {
+            ScanError::UnknownVariant { at_token, .. } => assert_eq!(at_token, 2),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn verbose_errors() {
+        let opts = ScanOptions::new().verbose_errors(true);
+
+        let err = opts.from_str::<Vec<u32>>("1 x 2 3\n4 5").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "could not parse `x` as u32 at token 2 near `x 2 3`"
+        );
+
+        let long = format!("1 x {}", "9 ".repeat(30));
+        let err = opts.from_str::<(u32, u32)>(&long).unwrap_err();
+        let near = err.near().unwrap();
+        assert_eq!(near.chars().count(), 40);
+        assert!(near.starts_with("x 9 9"));
+
+        // off by default, and never added to running out of input
+        assert_eq!(
+            from_str::<(u32, u32)>("1 x").unwrap_err().to_string(),
+            "could not parse `x` as u32 at token 2"
+        );
+        assert!(opts.from_str::<(u32, u32)>("1").unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn error_kinds() {
+        let eof = from_str::<u32>("  ").unwrap_err();
+        let bad = from_str::<u32>("one").unwrap_err();
+
+        assert!(eof.is_eof() && !eof.is_parse_error());
+        assert!(bad.is_parse_error() && !bad.is_eof());
+
+        let big = from_str::<u8>("300").unwrap_err();
+        assert!(big.is_parse_error());
+
+        let mut scanner = Scanner::new("1 2 x");
+        let mut sum = 0;
+        let err = loop {
+            match scanner.next::<u32>() {
+                Ok(n) => sum += n,
+                Err(e) => break e,
+            }
+        };
+
+        assert_eq!(sum, 3);
+        assert!(err.is_parse_error());
+        assert_eq!(err.clone().to_string(), err.to_string());
+    }
+
+    #[test]
+    fn error_eq() {
+        assert_eq!(from_str::<u32>("").unwrap_err(), ScanError::EOF);
+        assert_eq!(
+            from_str::<u8>("one").unwrap_err(),
+            ScanError::ParseFailed {
+                token: String::from("one"),
+                target: "u8",
+                at_token: 1,
+                near: None,
+            }
+        );
+        assert_ne!(from_str::<u8>("300").unwrap_err(), ScanError::EOF);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn io_errors() {
+        use std::error::Error;
+        use std::io;
+
+        let closed = ScanError::from(io::Error::new(io::ErrorKind::UnexpectedEof, "closed"));
+        let copy = closed.clone();
+        assert_eq!(copy.to_string(), closed.to_string());
+
+        // io errors are equal when their kinds are
+        let a = ScanError::from(io::Error::new(io::ErrorKind::NotFound, "a"));
+        let b = ScanError::from(io::Error::new(io::ErrorKind::NotFound, "b"));
+        let c = ScanError::from(io::Error::new(io::ErrorKind::InvalidData, "a"));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+
+        let source = a.source().unwrap();
+        assert_eq!(source.to_string(), "a");
+        assert!(source.downcast_ref::<io::Error>().is_some());
+    }
+
+    #[test]
+    fn error_source() {
+        use std::error::Error;
+
+        let utf8 = from_bytes::<u32>(b"\xff").unwrap_err();
+        assert!(utf8.source().is_some());
+        assert!(ScanError::EOF.source().is_none());
     }
 
     #[test]
@@ -341,6 +2882,247 @@ mod tests {
         assert_eq!(id, 64);
     }
 
+    #[test]
+    fn scan_macro_braces() {
+        let a: u32 = scan!("f({{}}) = {}" <- "f({}) = 3").unwrap();
+        let b: u32 = scan!("{{{}}}" <- "{5}").unwrap();
+        let c = scan!("{{{{{u32}}}}}" <- "{{7}}").unwrap();
+        let d = scan!("{{{}}}" <- "(5)").map(|n: u32| n).unwrap_err();
+
+        assert_eq!(a, 3);
+        assert_eq!(b, 5);
+        assert_eq!(c, 7);
+        assert!(matches!(
+            d,
+            ScanError::PatternMismatch { expected: '{', .. }
+        ));
+    }
+
+    #[test]
+    fn scan_macro_rest() {
+        let a = scan!("ERROR: {*}" <- "ERROR: disk \"sda\" is full\n").unwrap();
+        let (code, msg) = scan!("[{u32}] {*}" <- "[404] not found").unwrap();
+        let b = scan!("{}: {*}" <- "x: a b")
+            .map(|(k, v): (char, &str)| (k, v))
+            .unwrap();
+
+        assert_eq!(a, "disk \"sda\" is full");
+        assert_eq!((code, msg), (404, "not found"));
+        assert_eq!(b, ('x', "a b"));
+    }
+
+    #[test]
+    fn scan_macro_alternatives() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Edge {
+            from: u32,
+            to: u32,
+        }
+
+        let lines = ["1 -> 2", "3 to 4", "5 => 6"];
+        let a: Vec<(u32, u32)> = lines[..2]
+            .iter()
+            .map(|line| scan!(["{} -> {}", "{} to {}",] <- line).unwrap())
+            .collect();
+        let b = scan!(Edge; ["{} -> {}", "{} to {}"] <- lines[1]).unwrap();
+        let c = scan!(["{u32} -> {u32}", "{u32} to {u32}"] <- lines[2]).unwrap_err();
+
+        let d: Vec<Edge> = scan_all!(Edge; ["{} -> {}", "{} to {}"] <- "1 -> 2\n3 to 4")
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(a, [(1, 2), (3, 4)]);
+        assert_eq!(b, Edge { from: 3, to: 4 });
+        assert_eq!(d, [Edge { from: 1, to: 2 }, Edge { from: 3, to: 4 }]);
+        assert!(matches!(c, ScanError::PatternMismatch { .. }));
+    }
+
+    #[test]
+    fn patterns() {
+        let claim = Pattern::new("#{} @ {},{}: {}x{}");
+        let input = "#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 5,5: 2x2";
+
+        type Claim = (u32, u32, u32, u32, u32);
+        let claims: Vec<Result<Claim, _>> = input.lines().map(|line| claim.parse(line)).collect();
+
+        assert_eq!(claims[0].as_ref().unwrap(), &(1, 1, 3, 4, 4));
+        assert_eq!(claims[1].as_ref().unwrap(), &(2, 3, 1, 4, 4));
+        assert!(matches!(claims[2], Err(ScanError::PatternMismatch { .. })));
+
+        let exact = claim
+            .parse_exact::<(u32, u32)>("#1 @ 1,3: 4x4")
+            .unwrap_err();
+        assert!(matches!(exact, ScanError::TrailingTokens { .. }));
+    }
+
+    #[test]
+    fn scan_macro_exprs() {
+        let buf = String::from("1 -> 2");
+        let mut lines = "3 -> 4\n5 -> 6".lines();
+
+        let a: (u32, u32) = scan!("{} -> {}" <- &buf).unwrap();
+        // the input is only evaluated once
+        let b: (u32, u32) = scan!("{} -> {}" <- lines.next().unwrap()).unwrap();
+        let c = scan!("{u32} -> {u32}" <- "7 -> 8").unwrap();
+
+        assert_eq!(a, (1, 2));
+        assert_eq!(b, (3, 4));
+        assert_eq!(c, (7, 8));
+        assert_eq!(lines.next(), Some("5 -> 6"));
+    }
+
+    #[test]
+    fn scan_macro_let() {
+        fn parse(line: &str) -> Result<(u32, i32, char), ScanError> {
+            scan!(let (id, x, y) = "#{u32} at ({i32},{i32})" <- line);
+            scan!(let tag = "{char}" <- "t");
+
+            Ok((id, x + y, tag))
+        }
+
+        assert_eq!(parse("#4 at (1,-3)").unwrap(), (4, -2, 't'));
+        assert!(parse("#4 at 1,-3").is_err());
+    }
+
+    #[test]
+    fn scan_opt_macro() {
+        let a: Option<u32> = scan_opt!("{}!" <- "5!");
+        let b: Option<u32> = scan_opt!("{}!" <- "five!");
+        let c = scan_opt!(["{u8} {u8}", "{u8},{u8}"] <- "1,2");
+
+        assert_eq!(a, Some(5));
+        assert_eq!(b, None);
+        assert_eq!(c, Some((1, 2)));
+    }
+
+    #[test]
+    fn scan_macro_mismatch() {
+        let test = "Gaurd #5";
+        let err = scan!("Guard #{}" <- test).map(|n: u32| n).unwrap_err();
+
+        match err {
+            ScanError::PatternMismatch {
+                expected,
+                found,
+                offset,
+            } => assert_eq!((expected, found, offset), ('u', 'a', 1)),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let test = "1 fire damage";
+        let err = scan!("{} {} dmg" <- test)
+            .map(|(_, _): (u32, String)| ())
+            .unwrap_err();
+        assert!(err.to_string().contains("at byte 8"));
+    }
+
+    #[test]
+    fn scan_macro_typed() {
+        let test = "7: seven";
+
+        let (n, name) = scan!("{u32}: {String}" <- test).unwrap();
+        assert_eq!(n, 7);
+        assert_eq!(name, "seven");
+
+        let test = "-1 + 2";
+        let (a, b) = scan!("{} + {u8}" <- test).unwrap();
+        let _: i64 = a;
+        assert_eq!(b, 2);
+
+        let test = "value: 0.5";
+        let single = scan!("value: {f64}" <- test).unwrap();
+        assert_eq!(single, 0.5);
+    }
+
+    #[test]
+    fn scan_macro_skip() {
+        let test = "10 junk 20";
+        let (a, b): (u32, u32) = scan!("{} {_} {}" <- test).unwrap();
+        assert_eq!((a, b), (10, 20));
+
+        let test = "id=7 name=seven";
+        let id = scan!("id={u32} name={_}" <- test).unwrap();
+        assert_eq!(id, 7);
+    }
+
+    #[test]
+    fn scan_macro_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Claim {
+            id: u32,
+            start: (u32, u32),
+            dim: (u32, u32),
+        }
+
+        let test = "#1 @ 555,891: 18x12";
+        let claim = scan!(Claim; "#{} @ {},{}: {}x{}" <- test).unwrap();
+
+        assert_eq!(
+            claim,
+            Claim {
+                id: 1,
+                start: (555, 891),
+                dim: (18, 12),
+            }
+        );
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Pair {
+            a: u32,
+            b: u32,
+        }
+
+        // the number of captures is checked at runtime
+        let test = "1 2 3";
+        let extra = scan!(Pair; "{} {} {}" <- test).unwrap_err();
+        assert!(matches!(
+            extra,
+            ScanError::TrailingTokens { at_token: 3, .. }
+        ));
+
+        let test = "1";
+        assert!(scan!(Pair; "{}" <- test).unwrap_err().is_eof());
+
+        // with the fields listed, each takes a capture, checked at compile time
+        struct Rect {
+            id: u32,
+            x: u32,
+            y: u32,
+            w: u32,
+            h: u32,
+        }
+
+        let test = "#1 @ 555,891: 18x12";
+        let rect = scan!(Rect { id, x, y, w, h }; "#{} @ {},{}: {}x{}" <- test).unwrap();
+        assert_eq!(
+            (rect.id, rect.x, rect.y, rect.w, rect.h),
+            (1, 555, 891, 18, 12)
+        );
+
+        let pair = scan!(Pair { a, b }; "{} {_} {u32}" <- "1 junk 2").unwrap();
+        assert_eq!(pair, Pair { a: 1, b: 2 });
+
+        let sum = scan!(Pair { a, b, }; "{}-{}" <- "3-4").map(|p| p.a + p.b);
+        assert_eq!(sum, Ok(7));
+        assert!(scan!(Pair { a, b }; "{} {}" <- "1 x").is_err());
+    }
+
+    #[test]
+    fn scan_all_macro() {
+        let log = "Guard #10 begins shift\n\nGuard #99 begins shift\n";
+
+        let ids: Vec<u32> = scan_all!("Guard #{} begins shift" <- log)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(ids, vec![10, 99]);
+
+        let bad = "1 + 2\n3 - 4";
+        let results: Vec<Result<(u32, u32), _>> = scan_all!("{} + {}" <- bad).collect();
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
     #[test]
     fn scan_macro_enum() {
         #[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
@@ -363,15 +3145,143 @@ mod tests {
     }
 
     #[test]
-    fn parse_asm() {
+    fn untagged() {
         #[derive(Debug, Deserialize, PartialEq)]
         #[serde(untagged)]
+        enum Value {
+            Reg(char),
+            Lit(u8),
+            Float(f32),
+            Label(String),
+        }
+
+        let values: Vec<Value> = from_str("a 80 7 - 2.5 loop").unwrap();
+        assert_eq!(
+            values,
+            [
+                Value::Reg('a'),
+                Value::Lit(80),
+                Value::Lit(7),
+                Value::Reg('-'),
+                Value::Float(2.5),
+                Value::Label(String::from("loop")),
+            ]
+        );
+
+        // too big for `Lit`, so the next variant that takes an integer wins
+        assert_eq!(from_str::<Value>("300").unwrap(), Value::Float(300.0));
+
+        // extra data is thrown out, like for any other type
+        assert_eq!(from_str::<Value>("80 junk").unwrap(), Value::Lit(80));
+        assert_eq!(
+            from_str::<Option<Value>>("a b").unwrap(),
+            Some(Value::Reg('a'))
+        );
+
+        // or reported, when every token has to be used
+        match from_str_exact::<Value>("5 x").unwrap_err() {
+            ScanError::TrailingTokens {
+                token, at_token, ..
+            } => {
+                assert_eq!(token, "x");
+                assert_eq!(at_token, 2);
+            }
+            e => panic!("expected trailing tokens, got {:?}", e),
+        }
+        let mut records = RecordDeserializer::new("a 1\n7\n");
+        assert!(matches!(
+            records.next::<Value>(),
+            Err(ScanError::TrailingTokens { .. })
+        ));
+        assert_eq!(records.next::<Value>().unwrap(), Value::Lit(7));
+    }
+
+    #[test]
+    fn json_values() {
+        use serde_json::{json, Value};
+
+        let a: Value = from_str_value("1 two 3.0 -4 x").unwrap();
+        let b: Value = from_str(" 5\n").unwrap();
+        let c: (Value, Value) = from_str("1 a").unwrap();
+        let d: Vec<Value> = from_str("1.5 -").unwrap();
+
+        assert_eq!(a, serde_json::json!([1, "two", 3.0, -4, "x"]));
+        assert_eq!(b, serde_json::json!(5));
+        assert_eq!(c, (serde_json::json!(1), serde_json::json!("a")));
+        assert_eq!(d, [serde_json::json!(1.5), serde_json::json!("-")]);
+        assert!(from_str::<Value>("").unwrap_err().is_eof());
+
+        // anywhere else, a value is read a token at a time, and extra data is thrown out
+        let mut scanner = Scanner::new("1 two");
+        assert_eq!(scanner.next::<Value>().unwrap(), serde_json::json!(1));
+        assert_eq!(from_str::<Value>("1 two").unwrap(), serde_json::json!(1));
+        assert_eq!(
+            from_str::<Value>("\"a b\" c").unwrap(),
+            serde_json::json!("a b")
+        );
+
+        let mut buf = [Value::Null, Value::Null, Value::Null];
+        assert_eq!(fill_from_str("1 2 3", &mut buf), Ok(3));
+        assert_eq!(buf, [1, 2, 3]);
+
+        let grid: Vec<Vec<Value>> = from_grid("1 a\n2 b", 2, 2).unwrap();
+        assert_eq!(grid, [[json!(1), json!("a")], [json!(2), json!("b")]]);
+    }
+
+    #[test]
+    fn until_sentinel() {
+        let a: Vec<u32> = from_str_until(0, "3 1 4 0 9").unwrap();
+        let b: Vec<i64> = from_str_until(-1, "-1").unwrap();
+        let c: Vec<String> = from_str_until(String::from("end"), "a b end c").unwrap();
+
+        assert_eq!(a, [3, 1, 4]);
+        assert!(b.is_empty());
+        assert_eq!(c, ["a", "b"]);
+        assert!(from_str_until(0u32, "3 1 4").unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn json_prefixed_integers() {
+        use serde_json::{json, Value};
+
+        // prefixed integers read the same way as with an explicit integer type
+        let a: Value = from_str_value("0x10 -0b11 0o7 0xg").unwrap();
+        assert_eq!(a, json!([16, -3, 7, "0xg"]));
+        assert_eq!(from_str::<u32>("0x10"), Ok(16));
+
+        // with a radix, prefixes aren't allowed, so they're left as strings
+        let hex = ScanOptions::new().radix(16);
+        let b: Vec<Value> = hex.from_str("ff 0x10 1.5").unwrap();
+        assert_eq!(b, [json!(255), json!("0x10"), json!(1.5)]);
+        assert!(hex.from_str::<u32>("0x10").is_err());
+
+        // digit separators are taken out before classifying, like when reading an integer
+        let sep = ScanOptions::new().digit_separators("_");
+        let c: Vec<Value> = sep.from_str("1_000 -2_5.5 a_b _ 0x_ff").unwrap();
+        assert_eq!(
+            c,
+            [
+                json!(1000),
+                json!(-25.5),
+                json!("a_b"),
+                json!("_"),
+                json!(255)
+            ]
+        );
+        assert_eq!(sep.from_str::<u32>("1_000"), Ok(1000));
+        assert_eq!(from_str::<Value>("1_000").unwrap(), json!("1_000"));
+    }
+
+    #[test]
+    fn parse_asm() {
+        #[derive(Clone, Debug, Deserialize, PartialEq)]
+        #[serde(untagged)]
         enum Value {
             Lit(u8),
             Reg(char),
         }
 
-        #[derive(Debug, Deserialize, PartialEq)]
+        #[derive(Clone, Debug, Deserialize, PartialEq)]
         #[serde(rename_all = "snake_case")]
         enum Instr {
             Add(Value, Value),
@@ -399,6 +3309,22 @@ mod tests {
             .filter_map(|l| from_str(l).ok())
             .collect();
 
-        assert_eq!(program, expected)
+        assert_eq!(program, expected);
+
+        let input = "
+            load a 80
+            # comment
+            nop
+            add a b
+        ";
+
+        let program: Vec<Instr> = from_str_iter_lenient(input)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(program, [expected[0].clone(), expected[2].clone()]);
+
+        let bad: Vec<Result<Instr, _>> = from_str_iter_lenient("nop\nadd a 300\n").collect();
+        assert_eq!(bad.len(), 1);
+        assert!(bad[0].as_ref().unwrap_err().is_parse_error());
     }
 }
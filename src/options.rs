@@ -0,0 +1,221 @@
+use de::{Config, Deserializer};
+use errors::ScanError;
+use lib::string::{String, ToString};
+
+use serde::de::Deserialize;
+
+/// Options for customizing how input is parsed.
+///
+/// The default options parse the same way as `from_str`.
+///
+/// # Example
+///
+/// ```rust
+/// # use serde_scan::ScanOptions;
+/// let opts = ScanOptions::new().whitespace(" \t,");
+///
+/// let row: (u32, u32, String) = opts.from_str("1,2, three\nfour").unwrap();
+/// assert_eq!(row, (1, 2, String::from("three\nfour")));
+/// ```
+///
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    whitespace: Option<String>,
+    skip: String,
+    zero_width: bool,
+    single_line: bool,
+    comment: Option<char>,
+    config: Config,
+}
+
+impl ScanOptions {
+    pub fn new() -> Self {
+        ScanOptions::default()
+    }
+
+    /// Set the characters that seperate tokens, replacing the default of any whitespace.
+    pub fn whitespace(mut self, set: &str) -> Self {
+        self.whitespace = Some(set.to_string());
+        self
+    }
+
+    /// Seperate tokens on the given characters, in addition to whitespace.
+    pub fn also_skip(mut self, set: &str) -> Self {
+        self.skip.push_str(set);
+        self
+    }
+
+    /// Seperate tokens on zero width spaces and stray byte order marks, which aren't
+    /// counted as whitespace otherwise.
+    pub fn zero_width_spaces(mut self, yes: bool) -> Self {
+        self.zero_width = yes;
+        self
+    }
+
+    /// Only read from the first line of the input, so a value has to fit on a single line.
+    ///
+    /// A value that needs tokens from the next line fails with `ScanError::EOF`.
+    /// Blank lines before the first token are skipped.
+    pub fn single_line(mut self, yes: bool) -> Self {
+        self.single_line = yes;
+        self
+    }
+
+    /// Ignore everything from `prefix` to the end of the line.
+    pub fn comment_prefix(mut self, prefix: char) -> Self {
+        self.comment = Some(prefix);
+        self
+    }
+
+    /// Set the token that reads as `None` for an optional value. Defaults to `-`.
+    pub fn none_token(mut self, token: &str) -> Self {
+        self.config.none_token = token.to_string();
+        self
+    }
+
+    /// Start every sequence, like a `Vec`, with the number of elements, so it doesn't have
+    /// to be the last thing in the input. `(Vec<u32>, String)` can read `3 1 2 3 done`.
+    ///
+    /// Unlike `from_str_seq_prefixed`, this applies to nested sequences too.
+    pub fn counted_seqs(mut self, yes: bool) -> Self {
+        self.config.counted_seqs = yes;
+        self
+    }
+
+    /// Read each map entry from a single token, with the key and value split by `sep`,
+    /// like `key=value`.
+    ///
+    /// Struct fields are read the same way, matched by name, so they can be in any order.
+    pub fn kv_separator(mut self, sep: char) -> Self {
+        self.config.kv_separator = Some(sep);
+        self
+    }
+
+    /// Match enum variants regardless of ascii case, so `FIRE` and `Fire` both read as `Fire`.
+    pub fn case_insensitive_variants(mut self, yes: bool) -> Self {
+        self.config.case_insensitive_variants = yes;
+        self
+    }
+
+    /// Read every integer in the given radix, without a prefix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in the range from 2 to 36.
+    pub fn radix(mut self, radix: u32) -> Self {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in the range 2..=36, not {}",
+            radix
+        );
+        self.config.radix = Some(radix);
+        self
+    }
+
+    /// Read integers as fixed point decimals with `scale` digits after the decimal point,
+    /// so with a scale of 2, `12.34` reads as `1234` and `5` reads as `500`.
+    ///
+    /// Takes priority over `radix`, since the decimal point only makes sense in base 10.
+    pub fn decimal_scale(mut self, scale: u32) -> Self {
+        self.config.decimal_scale = Some(scale);
+        self
+    }
+
+    /// Ignore the given characters inside numbers, so with `_` and `,` as separators,
+    /// `1_000` and `1,000` both read as `1000`.
+    pub fn digit_separators(mut self, set: &str) -> Self {
+        self.config.digit_separators = set.to_string();
+        self
+    }
+
+    /// Make sure nothing follows a unit enum variant, so `help extra` can't be read as
+    /// `Command::Help`. Any tokens left over are a `ScanError::TrailingTokens`.
+    ///
+    /// Only a unit variant read as the whole value is checked, so a `(Command, u32)`
+    /// or a `Vec<Command>` still reads as usual.
+    pub fn strict_unit_variants(mut self, yes: bool) -> Self {
+        self.config.strict_unit_variants = yes;
+        self
+    }
+
+    /// Read a single character that isn't a digit as its code point, when reading an integer,
+    /// so `A` reads as `65`.
+    pub fn char_as_code(mut self, yes: bool) -> Self {
+        self.config.char_as_code = yes;
+        self
+    }
+
+    /// Read a `char` from a backslash escape, for characters that would otherwise be
+    /// separators. `\n`, `\r`, `\t`, `\0` and `\\` work like in Rust, and `\s` is a space.
+    pub fn char_escapes(mut self, yes: bool) -> Self {
+        self.config.char_escapes = yes;
+        self
+    }
+
+    /// Reject floats that are infinite or NaN.
+    ///
+    /// By default, floats are parsed with `str::parse`, which accepts `inf`, `infinity`
+    /// and `nan` in any case, with an optional sign.
+    pub fn reject_non_finite(mut self, yes: bool) -> Self {
+        self.config.reject_non_finite = yes;
+        self
+    }
+
+    /// Read byte buffers, like `serde_bytes::ByteBuf`, from the bytes of a single token.
+    ///
+    /// By default, byte buffers are read as a sequence of numbers, the same as a `Vec<u8>`.
+    pub fn raw_bytes(mut self, yes: bool) -> Self {
+        self.config.raw_bytes = yes;
+        self
+    }
+
+    /// Include the input around the token that failed in parse errors, like
+    /// ``could not parse `x` as u32 at token 2 near `x 2 3` ``.
+    ///
+    /// The context runs to the end of the line, up to 40 characters. It's off by default,
+    /// since it has to be copied out of the input.
+    pub fn verbose_errors(mut self, yes: bool) -> Self {
+        self.config.verbose_errors = yes;
+        self
+    }
+
+    /// Parse a string using these options.
+    pub fn from_str<'a, T: Deserialize<'a>>(&self, s: &'a str) -> Result<T, ScanError> {
+        let s = if self.single_line {
+            let s = s.trim_start_matches(['\r', '\n']);
+            s.split('\n').next().unwrap_or(s)
+        } else {
+            s
+        };
+
+        let mut in_comment = false;
+
+        // comments are treated as one long separator
+        let separator = |ch| {
+            if in_comment {
+                in_comment = ch != '\n';
+                true
+            } else if Some(ch) == self.comment {
+                in_comment = true;
+                true
+            } else {
+                self.is_separator(ch)
+            }
+        };
+
+        Deserializer::from_closure(separator, s)
+            .with_config(self.config.clone())
+            .deserialize()
+    }
+
+    fn is_separator(&self, ch: char) -> bool {
+        let whitespace = match self.whitespace {
+            Some(ref set) => set.contains(ch),
+            None => ch.is_whitespace(),
+        };
+
+        let zero_width = self.zero_width && (ch == '\u{200b}' || ch == '\u{feff}');
+
+        whitespace || zero_width || self.skip.contains(ch)
+    }
+}
@@ -0,0 +1,209 @@
+use de::{Config, Deserializer};
+use errors::ScanError;
+use lib::string::String;
+use lib::vec::Vec;
+
+use serde::de::Deserialize;
+
+/// Parse a string with a `scan!` pattern.
+pub fn from_pattern<'a, T: Deserialize<'a>>(pattern: &str, s: &'a str) -> Result<T, ScanError> {
+    Pattern::new(pattern).parse(s)
+}
+
+/// Parse a string with a `scan!` pattern, making sure every capture was used.
+pub fn from_pattern_exact<'a, T: Deserialize<'a>>(
+    pattern: &str,
+    s: &'a str,
+) -> Result<T, ScanError> {
+    Pattern::new(pattern).parse_exact(s)
+}
+
+/// A `scan!` pattern, prepared ahead of time.
+///
+/// Useful for applying the same pattern to many inputs, without working out
+/// the literal text of the pattern every time.
+///
+/// # Example
+///
+/// ```rust
+/// # use serde_scan::Pattern;
+/// let edge = Pattern::new("{} -> {}");
+///
+/// let a: (u32, u32) = edge.parse("1 -> 2").unwrap();
+/// let b: (char, char) = edge.parse("a -> b").unwrap();
+///
+/// assert_eq!(a, (1, 2));
+/// assert_eq!(b, ('a', 'b'));
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct Pattern {
+    /// literal text between placeholders, with `{{` and `}}` unescaped
+    segments: Vec<String>,
+    /// the pattern ends with a `{*}` placeholder
+    rest: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        let mut segments = Vec::new();
+        let mut segment = String::new();
+        let mut chars = pattern.chars().peekable();
+        let mut rest = false;
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' | '}' if chars.peek() == Some(&ch) => {
+                    chars.next();
+                    segment.push(ch);
+                }
+                '{' => {
+                    let placeholder: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                    rest = placeholder.trim() == "*";
+                    segments.push(segment);
+                    segment = String::new();
+                }
+                ch => segment.push(ch),
+            }
+        }
+        let rest = rest && segment.is_empty();
+        segments.push(segment);
+
+        Pattern { segments, rest }
+    }
+
+    /// Parse a string with this pattern.
+    ///
+    /// Captures not needed for parsing `T` are thrown out.
+    pub fn parse<'a, T: Deserialize<'a>>(&self, s: &'a str) -> Result<T, ScanError> {
+        let mut matcher = Matcher::new(self);
+        let s = self.input(s);
+
+        let value = {
+            let mut de = Deserializer::from_closure(|ch| matcher.step(ch), s).with_config(config());
+            let value = de.deserialize();
+
+            // run the rest of the input through the matcher, to check the whole pattern
+            de.skip_rest();
+            value
+        };
+
+        matcher.finish(value)
+    }
+
+    /// Parse a string with this pattern, making sure every capture was used.
+    pub fn parse_exact<'a, T: Deserialize<'a>>(&self, s: &'a str) -> Result<T, ScanError> {
+        let mut matcher = Matcher::new(self);
+        let s = self.input(s);
+
+        let value = {
+            let mut de = Deserializer::from_closure(|ch| matcher.step(ch), s).with_config(config());
+            let value = de.deserialize().and_then(|value| de.end().map(|_| value));
+
+            de.skip_rest();
+            value
+        };
+
+        matcher.finish(value)
+    }
+
+    /// a `{*}` placeholder takes the rest of the line, without the line ending
+    fn input<'a>(&self, s: &'a str) -> &'a str {
+        if self.rest {
+            s.trim_end_matches(['\r', '\n'])
+        } else {
+            s
+        }
+    }
+}
+
+/// the literal text of a pattern does the job of quotes
+fn config() -> Config {
+    Config {
+        quoted_strings: false,
+        ..Config::default()
+    }
+}
+
+/// Matches the input against the literal text of a `scan!` pattern.
+///
+/// Literal characters are treated as separators, in order, as they show up in the input.
+/// Any whitespace in the pattern matches any whitespace character.
+///
+/// Once the first character of a literal segment has matched, the rest of the segment
+/// must follow, otherwise the input doesn't match the pattern.
+struct Matcher<'p> {
+    segments: &'p [String],
+    segment: usize,
+    pos: usize,
+    /// whether we're partway through a segment, rather than inside a capture
+    anchored: bool,
+    offset: usize,
+    mismatch: Option<ScanError>,
+}
+
+impl<'p> Matcher<'p> {
+    fn new(pattern: &'p Pattern) -> Self {
+        let mut matcher = Matcher {
+            segments: &pattern.segments,
+            segment: 0,
+            pos: 0,
+            // the text before the first placeholder has to start the input
+            anchored: true,
+            offset: 0,
+            mismatch: None,
+        };
+        matcher.skip_empty();
+        matcher
+    }
+
+    fn skip_empty(&mut self) {
+        while self
+            .segments
+            .get(self.segment)
+            .is_some_and(|s| s.len() == self.pos)
+        {
+            self.segment += 1;
+            self.pos = 0;
+            self.anchored = false;
+        }
+    }
+
+    fn step(&mut self, ch: char) -> bool {
+        let offset = self.offset;
+        self.offset += ch.len_utf8();
+
+        if self.mismatch.is_some() {
+            return false;
+        }
+
+        let expected = match self.segments.get(self.segment) {
+            Some(s) => s[self.pos..].chars().next().unwrap(),
+            None => return false,
+        };
+
+        if ch == expected || expected.is_whitespace() && ch.is_whitespace() {
+            self.pos += expected.len_utf8();
+            self.anchored = true;
+            self.skip_empty();
+            true
+        } else {
+            if self.anchored {
+                self.mismatch = Some(ScanError::PatternMismatch {
+                    expected,
+                    found: ch,
+                    offset,
+                });
+            }
+            false
+        }
+    }
+
+    /// a mismatch explains any other error, so it takes priority
+    fn finish<T>(self, value: Result<T, ScanError>) -> Result<T, ScanError> {
+        match self.mismatch {
+            Some(e) => Err(e),
+            None => value,
+        }
+    }
+}
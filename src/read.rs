@@ -0,0 +1,138 @@
+use errors::ScanError;
+use from_str;
+
+use serde::de::DeserializeOwned;
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::iter;
+
+/// Get a line of input from stdin, and parse it.
+///
+/// Extra data not needed for parsing `T` is thrown out.
+///
+pub fn next_line<T: DeserializeOwned>() -> Result<T, ScanError> {
+    next_line_buf(&mut String::new())
+}
+
+/// Like `next_line`, but reads into the given buffer to save an allocation on every call.
+///
+/// The buffer is cleared first.
+///
+/// ```rust,no_run
+/// let mut buf = String::new();
+///
+/// for _ in 0..1000 {
+///     let n: u64 = serde_scan::next_line_buf(&mut buf).unwrap();
+///     println!("{}", n * 2);
+/// }
+/// ```
+///
+pub fn next_line_buf<T: DeserializeOwned>(buf: &mut String) -> Result<T, ScanError> {
+    buf.clear();
+    io::stdin().read_line(buf)?;
+
+    from_str(buf)
+}
+
+/// Get a record from stdin, and parse it.
+///
+/// Unlike `next_line`, a record can span multiple lines: lines are read until
+/// there are enough tokens to parse `T`. Extra data on the last line read is thrown out.
+///
+pub fn next_record<T: DeserializeOwned>() -> Result<T, ScanError> {
+    let input = io::stdin();
+    let mut lock = input.lock();
+
+    from_buf_read(&mut lock)
+}
+
+/// Parse every line of stdin, locking it once for the whole loop.
+///
+/// Blank lines are skipped. The iterator ends when stdin does.
+///
+/// ```rust,no_run
+/// for pair in serde_scan::lines::<(u32, u32)>() {
+///     let (a, b) = pair.unwrap();
+///     println!("{}", a + b);
+/// }
+/// ```
+///
+pub fn lines<T: DeserializeOwned>() -> impl Iterator<Item = Result<T, ScanError>> {
+    buf_read_lines(io::stdin().lock())
+}
+
+/// Parse the next `n` lines of stdin, locking it once for all of them.
+///
+/// Blank lines are skipped, and don't count towards `n`. Returns `ScanError::EOF` if stdin ends before `n` lines have been read.
+///
+/// ```rust,no_run
+/// let n: usize = serde_scan::next_line().unwrap();
+/// let pairs: Vec<(u32, u32)> = serde_scan::next_n_lines(n).unwrap();
+/// ```
+///
+pub fn next_n_lines<T: DeserializeOwned>(n: usize) -> Result<Vec<T>, ScanError> {
+    buf_read_n_lines(io::stdin().lock(), n)
+}
+
+pub(crate) fn buf_read_n_lines<R, T>(reader: R, n: usize) -> Result<Vec<T>, ScanError>
+where
+    R: BufRead,
+    T: DeserializeOwned,
+{
+    // `n` usually comes from the input too, so it can't be trusted with a huge allocation
+    let mut out = Vec::with_capacity(n.min(1024));
+
+    for line in buf_read_lines(reader).take(n) {
+        out.push(line?);
+    }
+
+    if out.len() < n {
+        return Err(ScanError::EOF);
+    }
+
+    Ok(out)
+}
+
+pub(crate) fn buf_read_lines<R, T>(mut reader: R) -> impl Iterator<Item = Result<T, ScanError>>
+where
+    R: BufRead,
+    T: DeserializeOwned,
+{
+    let mut buf = String::new();
+
+    iter::from_fn(move || loop {
+        buf.clear();
+
+        match reader.read_line(&mut buf) {
+            Ok(0) => return None,
+            // blank lines hold no values, so they're skipped
+            Ok(_) if buf.trim().is_empty() => continue,
+            Ok(_) => return Some(from_str(&buf)),
+            Err(e) => return Some(Err(e.into())),
+        }
+    })
+}
+
+/// Read and parse a value from any reader.
+///
+/// Input is read a line at a time until there are enough tokens to parse `T`.
+/// Extra data on the last line read is thrown out.
+///
+pub fn from_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T, ScanError> {
+    from_buf_read(&mut BufReader::new(reader))
+}
+
+fn from_buf_read<R: BufRead, T: DeserializeOwned>(reader: &mut R) -> Result<T, ScanError> {
+    let mut buf = String::new();
+
+    loop {
+        if reader.read_line(&mut buf)? == 0 {
+            return from_str(&buf);
+        }
+
+        match from_str(&buf) {
+            Err(ScanError::EOF) => continue,
+            res => return res,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use de::Deserializer;
+use errors::ScanError;
+
+use serde::de::Deserialize;
+
+use lib::str::Lines;
+
+/// A deserializer for line oriented data, where each line holds one record.
+///
+/// Unlike `from_str`, line endings are a hard boundary: every call to `next` reads
+/// exactly one line, and it's an error for a record to need more or fewer tokens
+/// than the line has. Blank lines are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # use serde_scan::RecordDeserializer;
+/// let mut records = RecordDeserializer::new("alice 30\n\nbob 25 extra\n");
+///
+/// let alice: (&str, u32) = records.next().unwrap();
+/// assert_eq!(alice, ("alice", 30));
+///
+/// let bob = records.next::<(&str, u32)>().unwrap_err();
+/// assert_eq!(records.line(), 3);
+/// assert!(bob.is_parse_error());
+///
+/// assert!(!records.remaining());
+/// ```
+///
+pub struct RecordDeserializer<'de> {
+    lines: Lines<'de>,
+    line: usize,
+}
+
+impl<'de> RecordDeserializer<'de> {
+    /// Create a deserializer over a string contaning one record per line.
+    pub fn new(s: &'de str) -> Self {
+        RecordDeserializer {
+            lines: s.lines(),
+            line: 0,
+        }
+    }
+
+    /// Parse the next record, from a single line.
+    ///
+    /// Returns `ScanError::EOF` if the line runs out of tokens, or if there are no lines left.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: Deserialize<'de>>(&mut self) -> Result<T, ScanError> {
+        let line = self.next_line().ok_or(ScanError::EOF)?;
+
+        let mut de = Deserializer::from_str(line);
+        let value = de.deserialize()?;
+
+        de.end().map(|_| value)
+    }
+
+    /// The line number, starting at 1, of the last record read.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Check if there are any records left to parse.
+    pub fn remaining(&self) -> bool {
+        self.lines.clone().any(|line| !line.trim().is_empty())
+    }
+
+    fn next_line(&mut self) -> Option<&'de str> {
+        for line in self.lines.by_ref() {
+            self.line += 1;
+
+            if !line.trim().is_empty() {
+                return Some(line);
+            }
+        }
+
+        None
+    }
+}
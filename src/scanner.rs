@@ -0,0 +1,77 @@
+use de::{Deserializer, StrTokens};
+use errors::ScanError;
+
+use serde::de::Deserialize;
+
+/// A reusable scanner over whitespace seperated data.
+///
+/// Each call to `next` picks up where the last one left off, so a single buffer
+/// can be parsed a piece at a time.
+///
+/// # Example
+///
+/// ```rust
+/// # use serde_scan::Scanner;
+/// let mut scanner = Scanner::new("2 10 20");
+///
+/// let n: usize = scanner.next().unwrap();
+/// let values: Vec<u32> = (0..n).map(|_| scanner.next().unwrap()).collect();
+///
+/// assert_eq!(values, [10, 20]);
+/// assert!(!scanner.remaining());
+/// ```
+///
+pub struct Scanner<'de> {
+    de: Deserializer<'de, StrTokens<'de>>,
+}
+
+impl<'de> Scanner<'de> {
+    /// Create a scanner over a string contaning whitespace seperated data.
+    pub fn new(s: &'de str) -> Self {
+        Scanner {
+            de: Deserializer::from_str(s),
+        }
+    }
+
+    /// Parse the next value.
+    ///
+    /// Tokens not needed for parsing `T` are left for the next call.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next<T: Deserialize<'de>>(&mut self) -> Result<T, ScanError> {
+        self.de.deserialize()
+    }
+
+    /// Check if there is any input left to parse.
+    pub fn remaining(&self) -> bool {
+        self.de.has_tokens()
+    }
+
+    /// Save the scanner's position, to go back to with `restore`.
+    ///
+    /// ```rust
+    /// # use serde_scan::Scanner;
+    /// let mut scanner = Scanner::new("abc 1");
+    /// let start = scanner.checkpoint();
+    ///
+    /// assert!(scanner.next::<u32>().is_err());
+    /// scanner.restore(&start);
+    ///
+    /// assert_eq!(scanner.next::<String>().unwrap(), "abc");
+    /// ```
+    pub fn checkpoint(&self) -> Checkpoint<'de> {
+        Checkpoint {
+            de: self.de.clone(),
+        }
+    }
+
+    /// Go back to a position saved with `checkpoint`, to parse the same input again.
+    pub fn restore(&mut self, checkpoint: &Checkpoint<'de>) {
+        self.de = checkpoint.de.clone();
+    }
+}
+
+/// A saved position in a [`Scanner`](struct.Scanner.html).
+#[derive(Clone)]
+pub struct Checkpoint<'de> {
+    de: Deserializer<'de, StrTokens<'de>>,
+}